@@ -2,6 +2,7 @@ use atom_syndication::Category as AtomCategory;
 use rss::Category as RssCategory;
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Category {
     pub name: String,
     // domain for RSS
@@ -9,6 +10,22 @@ pub struct Category {
     pub label: Option<String>,
 }
 
+// JSON Feed tags are plain strings
+impl From<String> for Category {
+    fn from(value: String) -> Self {
+        Self {
+            name: value,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Category> for String {
+    fn from(value: Category) -> Self {
+        value.name
+    }
+}
+
 impl From<RssCategory> for Category {
     fn from(cat: RssCategory) -> Self {
         // Adding label based on name is only
@@ -132,4 +149,18 @@ pub(crate) mod tests {
         cat2.label = Some(cat2.name.clone());
         assert_eq!(cat1, cat2);
     }
+    #[test]
+    fn abstract_to_string_equal() {
+        let cat: String = new_category().into();
+        assert_eq!(cat, String::from("Category"));
+    }
+    #[test]
+    fn string_to_abstact_equal() {
+        let cat1 = Category {
+            name: String::from("Category"),
+            ..Default::default()
+        };
+        let cat2: Category = String::from("Category").into();
+        assert_eq!(cat1, cat2);
+    }
 }
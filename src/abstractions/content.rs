@@ -1,6 +1,7 @@
 use atom_syndication::Content as AtomContent;
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Content {
     pub base: Option<String>,
     pub lang: Option<String>,
@@ -127,15 +128,19 @@ mod tests {
     #[test]
     fn string_to_abstract_equal() {
         let cont1: Content = String::from("Value").into();
-        let mut cont2 = Content::default();
-        cont2.value = Some(String::from("Value"));
+        let cont2 = Content {
+            value: Some(String::from("Value")),
+            ..Default::default()
+        };
         assert_eq!(cont1, cont2)
     }
     #[test]
     fn option_string_to_abstract_equal() {
         let cont1: Content = Some(String::from("Value")).into();
-        let mut cont2 = Content::default();
-        cont2.value = Some(String::from("Value"));
+        let cont2 = Content {
+            value: Some(String::from("Value")),
+            ..Default::default()
+        };
         assert_eq!(cont1, cont2)
     }
     #[test]
@@ -1,6 +1,9 @@
+use crate::abstractions::link::Link;
+use crate::jsonfeed::Attachment as JsonEnclosure;
 use rss::Enclosure as RssEnclosure;
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Enclosure {
     pub url: String,
     pub length: String,
@@ -27,6 +30,53 @@ impl From<Enclosure> for RssEnclosure {
     }
 }
 
+impl From<JsonEnclosure> for Enclosure {
+    fn from(value: JsonEnclosure) -> Self {
+        Self {
+            url: value.url,
+            length: value
+                .size_in_bytes
+                .map(|s| s.to_string())
+                .unwrap_or_default(),
+            mime_type: value.mime_type,
+        }
+    }
+}
+
+impl From<Enclosure> for JsonEnclosure {
+    fn from(value: Enclosure) -> Self {
+        Self {
+            url: value.url,
+            mime_type: value.mime_type,
+            size_in_bytes: value.length.parse().ok(),
+            ..Default::default()
+        }
+    }
+}
+
+// Atom carries enclosures as links with rel="enclosure"
+impl From<Enclosure> for Link {
+    fn from(value: Enclosure) -> Self {
+        Self {
+            href: value.url,
+            rel: "enclosure".into(),
+            mime_type: Some(value.mime_type).filter(|s| !s.is_empty()),
+            length: Some(value.length).filter(|s| !s.is_empty()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Link> for Enclosure {
+    fn from(value: Link) -> Self {
+        Self {
+            url: value.href,
+            length: value.length.unwrap_or_default(),
+            mime_type: value.mime_type.unwrap_or_default(),
+        }
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
@@ -44,6 +94,14 @@ pub(crate) mod tests {
             mime_type: "text/html".into(),
         }
     }
+    pub fn new_json_enclosure() -> JsonEnclosure {
+        JsonEnclosure {
+            url: "https://enclosure.org".into(),
+            mime_type: "text/html".into(),
+            size_in_bytes: Some(100),
+            ..Default::default()
+        }
+    }
     #[test]
     fn default_abstract_to_rss_equal() {
         let enc1: RssEnclosure = Enclosure::default().into();
@@ -68,4 +126,25 @@ pub(crate) mod tests {
         let enc2 = new_enclosure();
         assert_eq!(enc1, enc2);
     }
+    #[test]
+    fn abstract_to_json_equal() {
+        let enc1: JsonEnclosure = new_enclosure().into();
+        let enc2 = new_json_enclosure();
+        assert_eq!(enc1, enc2);
+    }
+    #[test]
+    fn json_to_abstract_equal() {
+        let enc1: Enclosure = new_json_enclosure().into();
+        let enc2 = new_enclosure();
+        assert_eq!(enc1, enc2);
+    }
+    #[test]
+    fn abstract_to_link_equal() {
+        let link: Link = new_enclosure().into();
+        assert_eq!(link.rel, "enclosure");
+        assert_eq!(link.length.as_deref(), Some("100"));
+        assert_eq!(link.mime_type.as_deref(), Some("text/html"));
+        let enc: Enclosure = link.into();
+        assert_eq!(enc, new_enclosure());
+    }
 }
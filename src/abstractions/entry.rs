@@ -4,27 +4,36 @@ use crate::abstractions::{
     category::Category,
     content::Content,
     enclosure::Enclosure,
-    extension::{Extension, ExtensionMap},
+    extension::{
+        dublin_core_from_extensions, dublin_core_to_extensions, extensions_from_json,
+        extensions_to_json, itunes_from_extensions, itunes_to_extensions, Extension, ExtensionMap,
+    },
+    feed::{link_from_extension, link_to_extension, person_from_extension, person_to_extension},
+    fingerprint::Fingerprint,
     guid::Guid,
     link::Link,
-    person::Person,
+    loss::{link_losses, nested, text_losses, Checked},
+    person::{avatar_losses, format_persons, Person, PersonFormat},
     source::Source,
-    text::Text,
+    text::{Text, TextType},
 };
+use crate::date::parse_date;
+use crate::jsonfeed::{Attachment as JsonEnclosure, Item as JsonEntry};
 use atom_syndication::{
     extension::{Extension as AtomExtension, ExtensionMap as AtomExtensionMap},
     Category as AtomCategory, Entry as AtomEntry, Link as AtomLink, Person as AtomAuthor,
     Text as AtomText,
 };
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, Utc};
 use rss::{
-    extension::dublincore::DublinCoreExtension,
-    extension::itunes::ITunesItemExtension,
+    extension::dublincore::{DublinCoreExtension, NAMESPACE as DUBLIN_CORE_NAMESPACE},
+    extension::itunes::{ITunesItemExtension, NAMESPACE as ITUNES_NAMESPACE},
     extension::{Extension as RssExtension, ExtensionMap as RssExtensionMap},
     Category as RssCategory, Enclosure as RssEnclosure, Guid as RssGuid, Item as RssEntry,
 };
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entry {
     pub title: Text,
     pub published: Option<DateTime<FixedOffset>>,
@@ -33,7 +42,7 @@ pub struct Entry {
     pub links: Vec<Link>,
     pub summary: Option<Text>,
     pub authors: Vec<Person>,
-    pub feed_authors: Option<Vec<Person>>,
+    pub contributors: Vec<Person>,
     pub categories: Vec<Category>,
     pub comments: Option<String>,
     pub enclosure: Option<Enclosure>,
@@ -45,8 +54,256 @@ pub struct Entry {
     pub dublin_core_ext: Option<DublinCoreExtension>,
 }
 
+impl Entry {
+    // The update date, without the placeholder the default
+    // entry carries for Atom round trips
+    pub fn updated_date(&self) -> Option<DateTime<FixedOffset>> {
+        self.updated.filter(|u| *u != DateTime::<Utc>::UNIX_EPOCH)
+    }
+
+    // The publication date, or the update date of entries
+    // that have none
+    pub fn date(&self) -> Option<DateTime<FixedOffset>> {
+        self.published.or(self.updated_date())
+    }
+
+    // Same as the From conversion, but the fields RSS has no
+    // element for are kept: rights and contributors with
+    // Dublin Core, the rest as Atom elements. From<RssEntry>
+    // reads them back.
+    pub fn into_rss_preserving(self) -> RssEntry {
+        self.into_rss_preserving_with_prefix("atom")
+    }
+
+    // Atom elements are written with the prefix the channel
+    // binds to the Atom namespace
+    pub(crate) fn into_rss_preserving_with_prefix(self, prefix: &str) -> RssEntry {
+        let mut atom: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+        // the first link is the item link, it is repeated
+        // when its attributes would be lost
+        let plain = self.links.first().map(|l| *l == Link::from(l.href.clone()));
+        let skip = if plain == Some(true) { 1 } else { 0 };
+        let links: Vec<Extension> = self
+            .links
+            .iter()
+            .skip(skip)
+            .map(|l| link_to_extension(l.clone(), prefix))
+            .collect();
+        if !links.is_empty() {
+            atom.insert("link".into(), links);
+        }
+        if let Some(updated) = self.updated_date() {
+            atom.insert(
+                "updated".into(),
+                vec![Extension {
+                    name: format!("{}:updated", prefix),
+                    value: Some(updated.to_rfc3339()),
+                    ..Default::default()
+                }],
+            );
+        }
+        // the author string only holds names and emails
+        if self.authors.iter().any(|p| p.uri.is_some()) {
+            atom.insert(
+                "author".into(),
+                self.authors
+                    .iter()
+                    .map(|p| person_to_extension(p.clone(), prefix, "author"))
+                    .collect(),
+            );
+        }
+        // dc:contributor is a string as well
+        let mut dc_contributors: Vec<String> = Vec::new();
+        if self.contributors.iter().any(|p| p.uri.is_some()) {
+            atom.insert(
+                "contributor".into(),
+                self.contributors
+                    .iter()
+                    .map(|p| person_to_extension(p.clone(), prefix, "contributor"))
+                    .collect(),
+            );
+        } else {
+            dc_contributors = self
+                .contributors
+                .iter()
+                .map(|p| p.format(PersonFormat::default()))
+                .collect();
+        }
+        let mut dc_rights: Option<String> = None;
+        match self.rights.clone() {
+            Some(rights) if rights == Text::from(rights.value.clone()) => {
+                dc_rights = Some(rights.value);
+            }
+            Some(rights) => {
+                let mut attrs: BTreeMap<String, String> = BTreeMap::new();
+                let text_type = match rights.text_type {
+                    TextType::Text => "text",
+                    TextType::Html => "html",
+                    TextType::Xhtml => "xhtml",
+                };
+                attrs.insert("type".into(), text_type.into());
+                atom.insert(
+                    "rights".into(),
+                    vec![Extension {
+                        name: format!("{}:rights", prefix),
+                        value: Some(rights.value),
+                        attrs,
+                        ..Default::default()
+                    }],
+                );
+            }
+            None => {}
+        }
+        let mut entry: RssEntry = self.into();
+        if let Some(rights) = dc_rights {
+            entry
+                .dublin_core_ext
+                .get_or_insert_with(Default::default)
+                .rights
+                .push(rights);
+        }
+        if !dc_contributors.is_empty() {
+            entry
+                .dublin_core_ext
+                .get_or_insert_with(Default::default)
+                .contributors
+                .extend(dc_contributors);
+        }
+        if !atom.is_empty() {
+            let ext = entry.extensions.entry(prefix.into()).or_default();
+            for (key, values) in atom {
+                ext.entry(key)
+                    .or_default()
+                    .extend(values.into_iter().map(|v| v.into()));
+            }
+        }
+        entry
+    }
+
+    // Same as the From conversion with the author field
+    // written in the given format
+    pub fn into_rss_with_format(self, format: PersonFormat) -> RssEntry {
+        let author = format_persons(&self.authors, format);
+        let mut item: RssEntry = self.into();
+        item.author = author;
+        item
+    }
+
+    pub fn to_rss_checked(&self) -> Checked<RssEntry> {
+        Checked {
+            value: self.clone().into(),
+            lost: self.rss_losses(),
+        }
+    }
+
+    pub fn to_atom_checked(&self) -> Checked<AtomEntry> {
+        Checked {
+            value: self.clone().into(),
+            lost: self.atom_losses(),
+        }
+    }
+
+    // Fields From<Entry> for RssEntry has nowhere to put
+    pub(crate) fn rss_losses(&self) -> Vec<String> {
+        let mut lost: Vec<String> = text_losses("title", &self.title);
+        if self.updated_date().is_some() {
+            lost.push("updated".into());
+        }
+        if self.rights.is_some() {
+            lost.push("rights".into());
+        }
+        if !self.contributors.is_empty() {
+            lost.push("contributors".into());
+        }
+        for (i, person) in self.authors.iter().enumerate() {
+            if person.uri.is_some() {
+                lost.push(format!("authors[{}].uri", i));
+            }
+        }
+        if let Some(link) = self.links.first() {
+            lost.extend(link_losses("links[0]", link));
+        }
+        for i in 1..self.links.len() {
+            lost.push(format!("links[{}]", i));
+        }
+        if let Some(summary) = &self.summary {
+            lost.extend(text_losses("summary", summary));
+        }
+        for (i, category) in self.categories.iter().enumerate() {
+            if category.label.is_some() {
+                lost.push(format!("categories[{}].label", i));
+            }
+        }
+        if let Some(source) = &self.source {
+            lost.extend(nested("source", source.rss_losses()));
+        }
+        if let Some(content) = &self.content {
+            if content.base.is_some() {
+                lost.push("content.base".into());
+            }
+            if content.lang.is_some() {
+                lost.push("content.lang".into());
+            }
+            if content.src.is_some() {
+                lost.push("content.src".into());
+            }
+            // content:encoded always holds HTML
+            let html = ["html", "text/html"];
+            if content
+                .content_type
+                .as_deref()
+                .is_some_and(|t| !html.contains(&t))
+            {
+                lost.push("content.content_type".into());
+            }
+        }
+        lost
+    }
+
+    // Same as the From conversion, with the fields of the
+    // item the abstraction has nowhere to put
+    pub fn from_json_checked(item: &JsonEntry) -> Checked<Entry> {
+        Checked {
+            value: item.clone().into(),
+            lost: Entry::json_losses(item),
+        }
+    }
+
+    pub(crate) fn json_losses(item: &JsonEntry) -> Vec<String> {
+        let mut lost: Vec<String> = avatar_losses(&item.authors, item.author.as_ref());
+        // content holds the HTML when an item has both
+        if item.content_html.is_some() && item.content_text.is_some() {
+            lost.push("content_text".into());
+        }
+        for (i, attachment) in item.attachments.iter().enumerate() {
+            if attachment.duration_in_seconds.is_some() {
+                lost.push(format!("attachments[{}].duration_in_seconds", i));
+            }
+        }
+        lost
+    }
+
+    // Fields From<Entry> for AtomEntry has nowhere to put
+    pub(crate) fn atom_losses(&self) -> Vec<String> {
+        let mut lost: Vec<String> = Vec::new();
+        // Atom ids always come back as permalinks
+        if !self.guid.permalink {
+            lost.push("guid.permalink".into());
+        }
+        lost
+    }
+}
+
 impl From<RssEntry> for Entry {
     fn from(value: RssEntry) -> Self {
+        Entry::from_rss_with_prefix(value, "atom")
+    }
+}
+
+impl Entry {
+    // Atom elements are read with the prefix the channel
+    // binds to the Atom namespace
+    pub(crate) fn from_rss_with_prefix(value: RssEntry, prefix: &str) -> Self {
         let title: Text = match value.title {
             Some(text) => text.into(),
             None => Text::default(),
@@ -55,27 +312,53 @@ impl From<RssEntry> for Entry {
             Some(id) => id.into(),
             None => Guid::default(),
         };
-        let links: Vec<Link> = match value.link {
+        let mut links: Vec<Link> = match value.link {
             Some(link) => {
                 vec![link.into()]
             }
             None => vec![Link::default()],
         };
         let summary: Option<Text> = value.description.map(|s| s.into());
-        let authors: Vec<Person> = match value.author {
-            Some(text) => text.split("; ").map(|s| s.to_string().into()).collect(),
+        let mut rights: Option<Text> = None;
+        let mut contributors: Vec<Person> = Vec::new();
+        let mut authors: Vec<Person> = match value.author {
+            Some(text) => Person::parse_list(&text),
             None => vec![],
         };
-        let categories: Vec<Category> = value.categories.into_iter().map(|s| s.into()).collect();
+        let mut categories: Vec<Category> =
+            value.categories.into_iter().map(|s| s.into()).collect();
         let enclosure: Option<Enclosure> = value.enclosure.map(|s| s.into());
-        let published: Option<DateTime<FixedOffset>> = match value.pub_date {
-            Some(str_date) => match DateTime::<FixedOffset>::parse_from_rfc2822(&str_date) {
-                Ok(re) => Some(re),
-                _ => None,
-            },
-            None => None,
-        };
-        let extensions: ExtensionMap = {
+        let mut published: Option<DateTime<FixedOffset>> =
+            value.pub_date.as_deref().and_then(parse_date);
+        // RSS 1.0 items carry authors, dates and subjects with
+        // Dublin Core, use them when the RSS field is missing
+        let mut dublin_core_ext = value.dublin_core_ext;
+        if let Some(dc) = dublin_core_ext.as_mut() {
+            if authors.is_empty() {
+                authors = dc.creators.drain(..).map(|s| s.into()).collect();
+            }
+            if published.is_none() {
+                published = dc.dates.first().and_then(|s| parse_date(s));
+                if published.is_some() {
+                    dc.dates.remove(0);
+                }
+            }
+            if categories.is_empty() {
+                categories = dc.subjects.drain(..).map(|s| s.into()).collect();
+            }
+            if !dc.rights.is_empty() {
+                rights = Some(dc.rights.remove(0).into());
+            }
+            contributors = dc
+                .contributors
+                .drain(..)
+                .map(|s| Person::parse(&s))
+                .collect();
+        }
+        if dublin_core_ext == Some(DublinCoreExtension::default()) {
+            dublin_core_ext = None;
+        }
+        let mut extensions: ExtensionMap = {
             let mut bt1: BTreeMap<String, BTreeMap<String, Vec<Extension>>> = BTreeMap::new();
             for (key, value) in value.extensions.into_iter() {
                 let mut bt2: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
@@ -90,26 +373,64 @@ impl From<RssEntry> for Entry {
             }
             bt1
         };
+        // Atom elements written by Entry::into_rss_preserving
+        let mut updated: Option<DateTime<FixedOffset>> = None;
+        if let Some(atom) = extensions.get_mut(prefix) {
+            for link in atom
+                .remove("link")
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(link_from_extension)
+            {
+                if links.len() == 1 && links[0] == Link::from(link.href.clone()) {
+                    links[0] = link;
+                } else {
+                    links.push(link);
+                }
+            }
+            updated = atom
+                .remove("updated")
+                .and_then(|u| u.into_iter().next())
+                .and_then(|u| u.value)
+                .and_then(|u| parse_date(&u));
+            if let Some(ext) = atom.remove("rights").and_then(|r| r.into_iter().next()) {
+                rights = Some(Text {
+                    text_type: match ext.attrs.get("type").map(|t| t.as_str()) {
+                        Some("html") => TextType::Html,
+                        Some("xhtml") => TextType::Xhtml,
+                        _ => TextType::Text,
+                    },
+                    ..ext.value.unwrap_or_default().into()
+                });
+            }
+            if let Some(persons) = atom.remove("author") {
+                authors = persons.into_iter().map(person_from_extension).collect();
+            }
+            if let Some(persons) = atom.remove("contributor") {
+                contributors = persons.into_iter().map(person_from_extension).collect();
+            }
+            if atom.is_empty() {
+                extensions.remove(prefix);
+            }
+        }
         Self {
             title,
             guid,
             links,
             summary,
             authors,
-            // data not available in that case
-            feed_authors: None,
+            contributors,
             categories,
             comments: value.comments,
             enclosure,
             source: value.source.map(|s| s.into()),
             content: value.content.map(|s| s.into()),
             published,
-            // no equivalent field on RSS item
-            updated: None,
-            rights: None,
+            updated,
+            rights,
             extensions,
             itunes_ext: value.itunes_ext,
-            dublin_core_ext: value.dublin_core_ext,
+            dublin_core_ext,
         }
     }
 }
@@ -132,12 +453,7 @@ impl From<Entry> for RssEntry {
             Some(value.links[0].clone().into())
         };
         let description: Option<String> = value.summary.map(|s| s.into());
-        let author: Option<String> = if value.authors.is_empty() {
-            None
-        } else {
-            let ast: Vec<String> = value.authors.into_iter().map(|s| s.into()).collect();
-            Some(ast.join("; "))
-        };
+        let author: Option<String> = format_persons(&value.authors, PersonFormat::default());
         let categories: Vec<RssCategory> = value.categories.into_iter().map(|s| s.into()).collect();
         let enclosure: Option<RssEnclosure> = value.enclosure.map(|s| s.into());
         let extensions: RssExtensionMap = {
@@ -176,16 +492,24 @@ impl From<Entry> for RssEntry {
 
 impl From<AtomEntry> for Entry {
     fn from(entry: AtomEntry) -> Self {
-        let links: Vec<Link> = entry.links.into_iter().map(|s| s.into()).collect();
-        let authors: Vec<Person> = entry.contributors.into_iter().map(|s| s.into()).collect();
-        let feed_authors: Option<Vec<Person>> = if entry.authors.is_empty() {
-            None
-        } else {
-            Some(entry.authors.into_iter().map(|s| s.into()).collect())
-        };
+        let mut links: Vec<Link> = entry.links.into_iter().map(|s| s.into()).collect();
+        // The first enclosure and comments page links are
+        // the RSS enclosure and comments
+        let enclosure: Option<Enclosure> = links
+            .iter()
+            .position(|l| l.rel == "enclosure")
+            .map(|i| links.remove(i).into());
+        let comments: Option<String> = links
+            .iter()
+            .position(|l| {
+                l.rel == "replies" && l.mime_type.as_deref().is_none_or(|t| t == "text/html")
+            })
+            .map(|i| links.remove(i).href);
+        let authors: Vec<Person> = entry.authors.into_iter().map(|s| s.into()).collect();
+        let contributors: Vec<Person> = entry.contributors.into_iter().map(|s| s.into()).collect();
         let summary: Option<Text> = entry.summary.map(|s| s.into());
         let categories: Vec<Category> = entry.categories.into_iter().map(|s| s.into()).collect();
-        let extensions: ExtensionMap = {
+        let mut extensions: ExtensionMap = {
             let mut bt1: BTreeMap<String, BTreeMap<String, Vec<Extension>>> = BTreeMap::new();
             for (key, value) in entry.extensions.into_iter() {
                 let mut bt2: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
@@ -200,48 +524,76 @@ impl From<AtomEntry> for Entry {
             }
             bt1
         };
+        let itunes_ext = extensions.remove("itunes").map(itunes_from_extensions);
+        let dublin_core_ext = extensions.remove("dc").map(dublin_core_from_extensions);
         Self {
             title: entry.title.into(),
             guid: entry.id.into(),
             links,
             summary,
             authors,
-            feed_authors,
+            contributors,
             categories,
-            // Do not exist in Atom entry
-            comments: None,
-            enclosure: None,
+            comments,
+            enclosure,
             source: entry.source.map(|s| s.into()),
             content: entry.content.map(|s| s.into()),
             published: entry.published,
             updated: Some(entry.updated),
             rights: entry.rights.map(|s| s.into()),
             extensions,
-            itunes_ext: None,
-            dublin_core_ext: None,
+            itunes_ext,
+            dublin_core_ext,
         }
     }
 }
 
 impl From<Entry> for AtomEntry {
     fn from(value: Entry) -> Self {
-        let links: Vec<AtomLink> = value.links.into_iter().map(|s| s.into()).collect();
-        let authors: Vec<AtomAuthor> = if let Some(feed_authors) = value.feed_authors {
-            feed_authors.into_iter().map(|s| s.into()).collect()
-        } else {
-            vec![]
-        };
-        let contributors: Vec<AtomAuthor> = value.authors.into_iter().map(|s| s.into()).collect();
+        let mut links: Vec<AtomLink> = value.links.into_iter().map(|s| s.into()).collect();
+        if let Some(enclosure) = value.enclosure {
+            links.push(Link::from(enclosure).into());
+        }
+        if let Some(comments) = value.comments {
+            links.push(AtomLink {
+                href: comments,
+                rel: "replies".into(),
+                mime_type: Some("text/html".into()),
+                ..Default::default()
+            });
+        }
+        let authors: Vec<AtomAuthor> = value.authors.into_iter().map(|s| s.into()).collect();
+        let contributors: Vec<AtomAuthor> =
+            value.contributors.into_iter().map(|s| s.into()).collect();
         let summary: Option<AtomText> = value.summary.map(|s| s.into());
         let categories: Vec<AtomCategory> =
             value.categories.into_iter().map(|s| s.into()).collect();
-        let updated = match value.updated {
-            Some(d) => d,
-            None => DateTime::<FixedOffset>::default(),
-        };
+        let updated = value.updated.unwrap_or_default();
+        let mut extensions = value.extensions;
+        let mut namespaces: BTreeMap<String, String> = BTreeMap::new();
+        if let Some(itunes) = value.itunes_ext {
+            let itunes = itunes_to_extensions(itunes);
+            if !itunes.is_empty() {
+                extensions
+                    .entry("itunes".into())
+                    .or_default()
+                    .extend(itunes);
+                namespaces.insert("itunes".into(), ITUNES_NAMESPACE.into());
+            }
+        }
+        if let Some(dublin_core) = value.dublin_core_ext {
+            let dublin_core = dublin_core_to_extensions(dublin_core);
+            if !dublin_core.is_empty() {
+                extensions
+                    .entry("dc".into())
+                    .or_default()
+                    .extend(dublin_core);
+                namespaces.insert("dc".into(), DUBLIN_CORE_NAMESPACE.into());
+            }
+        }
         let extensions: AtomExtensionMap = {
             let mut bt1: BTreeMap<String, BTreeMap<String, Vec<AtomExtension>>> = BTreeMap::new();
-            for (key, value) in value.extensions.into_iter() {
+            for (key, value) in extensions.into_iter() {
                 let mut bt2: BTreeMap<String, Vec<AtomExtension>> = BTreeMap::new();
                 for (key1, value1) in value.into_iter() {
                     let mut ve: Vec<AtomExtension> = Vec::new();
@@ -268,6 +620,158 @@ impl From<Entry> for AtomEntry {
             updated,
             rights: value.rights.map(|s| s.into()),
             extensions,
+            namespaces,
+        }
+    }
+}
+
+impl From<JsonEntry> for Entry {
+    fn from(value: JsonEntry) -> Self {
+        let guid = Guid {
+            permalink: value.url.as_ref() == Some(&value.id),
+            value: value.id,
+        };
+        let mut links: Vec<Link> = Vec::new();
+        if let Some(url) = value.url {
+            links.push(url.into());
+        }
+        if let Some(url) = value.external_url {
+            links.push(Link {
+                href: url,
+                rel: "related".into(),
+                ..Default::default()
+            });
+        }
+        // Only one enclosure on abstract entry, other attachments
+        // are kept as enclosure links
+        let mut attachments = value.attachments.into_iter();
+        let enclosure: Option<Enclosure> = attachments.next().map(|s| s.into());
+        links.extend(attachments.map(|s| Link {
+            href: s.url,
+            rel: "enclosure".into(),
+            mime_type: Some(s.mime_type),
+            title: s.title,
+            length: s.size_in_bytes.map(|s| s.to_string()),
+            ..Default::default()
+        }));
+        // The item images are kept as links too
+        let images = [("image", value.image), ("banner-image", value.banner_image)];
+        for (rel, image) in images {
+            if let Some(url) = image {
+                links.push(Link {
+                    href: url,
+                    rel: rel.into(),
+                    ..Default::default()
+                });
+            }
+        }
+        let content: Option<Content> = match (value.content_html, value.content_text) {
+            (Some(html), _) => Some(Content {
+                value: Some(html),
+                content_type: Some("html".into()),
+                lang: value.language,
+                ..Default::default()
+            }),
+            (None, Some(text)) => Some(Content {
+                value: Some(text),
+                content_type: Some("text".into()),
+                lang: value.language,
+                ..Default::default()
+            }),
+            (None, None) => None,
+        };
+        let mut authors: Vec<Person> = value.authors.into_iter().map(|s| s.into()).collect();
+        if authors.is_empty() {
+            authors.extend(value.author.map(|s| s.into()));
+        }
+        Self {
+            title: value.title.unwrap_or_default().into(),
+            published: value.date_published.as_deref().and_then(parse_date),
+            updated: value.date_modified.as_deref().and_then(parse_date),
+            guid,
+            links,
+            summary: value.summary.map(|s| s.into()),
+            authors,
+            // no equivalent field on JSON Feed item
+            contributors: vec![],
+            categories: value.tags.into_iter().map(|s| s.into()).collect(),
+            comments: None,
+            enclosure,
+            source: None,
+            content,
+            rights: None,
+            extensions: extensions_from_json(value.extensions),
+            itunes_ext: None,
+            dublin_core_ext: None,
+        }
+    }
+}
+
+impl From<Entry> for JsonEntry {
+    fn from(value: Entry) -> Self {
+        let date_modified: Option<String> = value.updated_date().map(|s| s.to_rfc3339());
+        // JSON Feed requires an id, the link or the content
+        // hash of the entry stands in for a missing guid
+        let id: String = if value.guid.value.trim().is_empty() {
+            match value.fingerprint() {
+                Fingerprint::Link(link) => link,
+                fingerprint => fingerprint.to_string(),
+            }
+        } else {
+            value.guid.value.clone()
+        };
+        let title: Option<String> = if value.title.value.is_empty() {
+            None
+        } else {
+            Some(value.title.into())
+        };
+        let mut url: Option<String> = None;
+        let mut external_url: Option<String> = None;
+        let (mut image, mut banner_image) = (None, None);
+        let mut attachments: Vec<JsonEnclosure> =
+            value.enclosure.into_iter().map(|s| s.into()).collect();
+        for link in value.links {
+            match link.rel.as_str() {
+                "alternate" if url.is_none() => url = Some(link.href),
+                "related" if external_url.is_none() => external_url = Some(link.href),
+                "image" if image.is_none() => image = Some(link.href),
+                "banner-image" if banner_image.is_none() => banner_image = Some(link.href),
+                "enclosure" => attachments.push(JsonEnclosure {
+                    url: link.href,
+                    mime_type: link.mime_type.unwrap_or_default(),
+                    title: link.title,
+                    size_in_bytes: link.length.and_then(|s| s.parse().ok()),
+                    ..Default::default()
+                }),
+                _ => {}
+            }
+        }
+        let (mut content_html, mut content_text, mut language) = (None, None, None);
+        if let Some(content) = value.content {
+            language = content.lang;
+            match content.content_type.as_deref() {
+                Some("text") | Some("text/plain") => content_text = content.value,
+                _ => content_html = content.value,
+            }
+        }
+        Self {
+            id,
+            url,
+            external_url,
+            title,
+            content_html,
+            content_text,
+            summary: value.summary.map(|s| s.into()),
+            image,
+            banner_image,
+            date_published: value.published.map(|s| s.to_rfc3339()),
+            date_modified,
+            authors: value.authors.into_iter().map(|s| s.into()).collect(),
+            tags: value.categories.into_iter().map(|s| s.into()).collect(),
+            language,
+            attachments,
+            extensions: extensions_to_json(value.extensions),
+            ..Default::default()
         }
     }
 }
@@ -283,7 +787,7 @@ impl Default for Entry {
             links: Vec::default(),
             summary: Option::default(),
             authors: Vec::default(),
-            feed_authors: Option::default(),
+            contributors: Vec::default(),
             categories: Vec::default(),
             comments: Option::default(),
             enclosure: Option::default(),
@@ -312,7 +816,7 @@ mod tests {
             links: vec![crate::abstractions::link::tests::new_link()],
             summary: Some(crate::abstractions::text::tests::new_text()),
             authors: vec![crate::abstractions::person::tests::new_person()],
-            feed_authors: Some(vec![crate::abstractions::person::tests::new_person()]),
+            contributors: vec![crate::abstractions::person::tests::new_person()],
             categories: vec![crate::abstractions::category::tests::new_category()],
             comments: Some("comments".into()),
             enclosure: Some(crate::abstractions::enclosure::tests::new_enclosure()),
@@ -333,4 +837,310 @@ mod tests {
         let entry2 = Entry::default();
         assert_eq!(entry1, entry2);
     }
+    #[test]
+    fn json_no_loss() {
+        let entry1 = JsonEntry {
+            id: "https://example.org/1".into(),
+            url: Some("https://example.org/1".into()),
+            external_url: Some("https://example.com".into()),
+            title: Some("Title".into()),
+            content_text: Some("Content".into()),
+            summary: Some("Summary".into()),
+            image: Some("https://example.org/1.png".into()),
+            banner_image: Some("https://example.org/1-banner.png".into()),
+            date_published: Some("2024-01-01T00:00:00+00:00".into()),
+            date_modified: Some("2024-01-02T00:00:00+02:00".into()),
+            authors: vec![crate::abstractions::person::tests::new_json_person()],
+            tags: vec!["Category".into()],
+            language: Some("en".into()),
+            attachments: vec![
+                crate::abstractions::enclosure::tests::new_json_enclosure(),
+                crate::abstractions::enclosure::tests::new_json_enclosure(),
+            ],
+            ..Default::default()
+        };
+        let entry2: Entry = entry1.clone().into();
+        assert!(entry2.guid.permalink);
+        assert!(entry2.enclosure.is_some());
+        let entry2: JsonEntry = entry2.into();
+        assert_eq!(entry1, entry2);
+        assert!(Entry::from_json_checked(&entry1).is_lossless());
+    }
+    #[test]
+    fn json_losses() {
+        let mut attachment = crate::abstractions::enclosure::tests::new_json_enclosure();
+        attachment.duration_in_seconds = Some(42.0);
+        let item = JsonEntry {
+            id: "1".into(),
+            content_html: Some("<p>Content</p>".into()),
+            content_text: Some("Content".into()),
+            attachments: vec![attachment],
+            ..Default::default()
+        };
+        let checked = Entry::from_json_checked(&item);
+        assert_eq!(
+            checked.lost,
+            vec!["content_text", "attachments[0].duration_in_seconds"]
+        );
+        let item = JsonEntry {
+            id: "1".into(),
+            authors: vec![
+                crate::abstractions::person::tests::new_json_person(),
+                crate::jsonfeed::Author {
+                    name: Some("Jane".into()),
+                    avatar: Some("https://example.org/jane.png".into()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(Entry::json_losses(&item), vec!["authors[1].avatar"]);
+        let content = checked.value.content.unwrap();
+        assert_eq!(content.value.as_deref(), Some("<p>Content</p>"));
+    }
+    #[test]
+    fn json_default_date_not_written() {
+        let item: JsonEntry = Entry::default().into();
+        assert_eq!(item.date_modified, None);
+    }
+    #[test]
+    fn json_id_fallback() {
+        let entry = Entry {
+            links: vec!["https://example.org/1#top".to_string().into()],
+            ..Default::default()
+        };
+        let item: JsonEntry = entry.into();
+        assert_eq!(item.id, "https://example.org/1");
+        let entry = Entry {
+            title: "Title".to_string().into(),
+            ..Default::default()
+        };
+        let item: JsonEntry = entry.clone().into();
+        assert_eq!(item.id, entry.fingerprint().to_string());
+        assert!(item.id.starts_with("hash:"));
+    }
+    #[test]
+    fn rss_dublin_core_promoted() {
+        let entry1 = RssEntry {
+            dublin_core_ext: Some(DublinCoreExtension {
+                creators: vec!["John".into()],
+                dates: vec!["2024-01-01T10:00:00+01:00".into()],
+                subjects: vec!["rust".into()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let entry2: Entry = entry1.into();
+        assert_eq!(entry2.authors[0].name, "John");
+        assert_eq!(
+            entry2.published.unwrap().to_rfc3339(),
+            "2024-01-01T10:00:00+01:00"
+        );
+        assert_eq!(entry2.categories[0].name, "rust");
+        assert_eq!(entry2.dublin_core_ext, None);
+    }
+    #[test]
+    fn abstract_to_rss_losses() {
+        let entry = Entry {
+            updated: Some(DateTime::<FixedOffset>::from_str("2024-01-01T00:00:00Z").unwrap()),
+            rights: Some("Rights".to_string().into()),
+            links: vec!["a".to_string().into(), "b".to_string().into()],
+            categories: vec![Category {
+                name: "rust".into(),
+                scheme: None,
+                label: Some("Rust".into()),
+            }],
+            ..Default::default()
+        };
+        let checked = entry.to_rss_checked();
+        assert_eq!(
+            checked.lost,
+            vec!["updated", "rights", "links[1]", "categories[0].label"]
+        );
+        assert_eq!(checked.value.link.as_deref(), Some("a"));
+        assert!(Entry::default().to_rss_checked().is_lossless());
+    }
+    #[test]
+    fn entry_date() {
+        assert_eq!(Entry::default().updated_date(), None);
+        assert_eq!(Entry::default().date(), None);
+        let updated = DateTime::<FixedOffset>::from_str("2024-01-02T00:00:00Z").unwrap();
+        let mut entry = Entry {
+            updated: Some(updated),
+            ..Default::default()
+        };
+        assert_eq!(entry.date(), Some(updated));
+        let published = DateTime::<FixedOffset>::from_str("2024-01-01T00:00:00Z").unwrap();
+        entry.published = Some(published);
+        assert_eq!(entry.date(), Some(published));
+        assert_eq!(entry.updated_date(), Some(updated));
+    }
+    #[test]
+    fn abstract_to_rss_text_losses() {
+        let entry = Entry {
+            title: crate::abstractions::text::tests::new_text(),
+            summary: Some(Text {
+                value: "<p>Summary</p>".into(),
+                text_type: TextType::Html,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            entry.rss_losses(),
+            vec![
+                "title.base",
+                "title.lang",
+                "title.text_type",
+                "summary.text_type"
+            ]
+        );
+    }
+    #[test]
+    fn abstract_to_rss_content_losses() {
+        let mut entry = Entry {
+            content: Some(Content {
+                value: Some("<p>Content</p>".into()),
+                content_type: Some("html".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(entry.to_rss_checked().is_lossless());
+        entry.content = Some(Content {
+            value: Some("Content".into()),
+            lang: Some("en".into()),
+            content_type: Some("text".into()),
+            ..Default::default()
+        });
+        assert_eq!(
+            entry.rss_losses(),
+            vec!["content.lang", "content.content_type"]
+        );
+    }
+    #[test]
+    fn abstract_to_rss_link_losses() {
+        let entry = Entry {
+            links: vec![Link {
+                href: "https://example.org/a".into(),
+                mime_type: Some("text/html".into()),
+                title: Some("A".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            entry.rss_losses(),
+            vec!["links[0].mime_type", "links[0].title"]
+        );
+    }
+    #[test]
+    fn abstract_to_rss_source_losses() {
+        let entry = Entry {
+            source: Some(Source {
+                title: Some("Origin".to_string().into()),
+                id: Some("urn:origin".into()),
+                links: vec!["https://example.org/feed".to_string().into()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(entry.rss_losses(), vec!["source.id"]);
+    }
+    #[test]
+    fn abstract_to_atom_losses() {
+        let entry = Entry {
+            guid: Guid {
+                value: "1234".into(),
+                permalink: false,
+            },
+            comments: Some("https://example.org/comments".into()),
+            itunes_ext: Some(ITunesItemExtension::default()),
+            ..Default::default()
+        };
+        let checked = entry.to_atom_checked();
+        assert_eq!(checked.lost, vec!["guid.permalink"]);
+        assert!(checked.strict().is_err());
+    }
+    #[test]
+    fn rss_preserving_no_loss() {
+        let entry1 = Entry {
+            title: "Title".to_string().into(),
+            updated: Some(DateTime::<FixedOffset>::from_str("2024-01-01T00:00:00Z").unwrap()),
+            rights: Some("Rights".to_string().into()),
+            links: vec![
+                "https://example.org/a".to_string().into(),
+                "https://example.org/b".to_string().into(),
+            ],
+            authors: vec![crate::abstractions::person::tests::new_person()],
+            contributors: vec!["Jane".to_string().into()],
+            ..Default::default()
+        };
+        let item = entry1.clone().into_rss_preserving();
+        assert_eq!(item.author.as_deref(), Some("john@lenon.com (John)"));
+        assert_eq!(
+            item.dublin_core_ext.as_ref().unwrap().rights,
+            vec!["Rights".to_string()]
+        );
+        assert_eq!(item.extensions["atom"]["link"].len(), 1);
+        assert_eq!(
+            item.dublin_core_ext.as_ref().unwrap().contributors,
+            vec!["Jane".to_string()]
+        );
+        assert!(!item.extensions["atom"].contains_key("contributor"));
+        let entry2: Entry = item.into();
+        assert_eq!(entry1, entry2);
+    }
+    #[test]
+    fn rss_sloppy_pub_date() {
+        let entry1 = RssEntry {
+            pub_date: Some("Mon, 01 Jan 2024 10:00:00 GMT+2".into()),
+            ..Default::default()
+        };
+        let entry2: Entry = entry1.into();
+        assert_eq!(
+            entry2.published.unwrap().to_rfc3339(),
+            "2024-01-01T10:00:00+02:00"
+        );
+    }
+    #[test]
+    fn rss_authors() {
+        let item1 = RssEntry {
+            author: Some("Jane Doe <jane@example.org>, john@example.org (John)".into()),
+            ..Default::default()
+        };
+        let entry: Entry = item1.into();
+        assert_eq!(entry.authors[0].name, "Jane Doe");
+        assert_eq!(entry.authors[1].email.as_deref(), Some("john@example.org"));
+        let item2: RssEntry = entry.clone().into();
+        let entry2: Entry = item2.into();
+        assert_eq!(entry.authors, entry2.authors);
+        let item3 = entry.into_rss_with_format(PersonFormat::NameEmail);
+        assert_eq!(
+            item3.author.as_deref(),
+            Some("Jane Doe <jane@example.org>; John <john@example.org>")
+        );
+    }
+    #[test]
+    fn rdf_no_loss() {
+        let feed1 = crate::rdf::read_from(crate::rdf::tests::FEED.as_bytes()).unwrap();
+        let entry: Entry = feed1.items[0].clone().into();
+        let item: RssEntry = entry.into();
+        let feed2 = rss::Channel {
+            items: vec![item],
+            ..feed1.clone()
+        };
+        let buf = crate::rdf::write_to(&feed2, Vec::new()).unwrap();
+        let feed2 = crate::rdf::read_from(&buf[..]).unwrap();
+        assert_eq!(feed1.items, feed2.items);
+    }
+    #[test]
+    fn malformed_date_ignored() {
+        let item = RssEntry {
+            pub_date: Some("1 Jan 2024 10:00 +é1".into()),
+            ..Default::default()
+        };
+        let entry: Entry = item.into();
+        assert_eq!(entry.published, None);
+    }
 }
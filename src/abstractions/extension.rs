@@ -1,8 +1,12 @@
 use atom_syndication::extension::Extension as AtomExtension;
-use rss::extension::Extension as RssExtension;
+use rss::extension::{
+    dublincore::DublinCoreExtension, itunes::ITunesItemExtension, Extension as RssExtension,
+};
+use serde_json::{Map, Value};
 use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Extension {
     pub name: String,
     pub value: Option<String>,
@@ -87,3 +91,220 @@ impl From<AtomExtension> for Extension {
 }
 
 pub type ExtensionMap = BTreeMap<String, BTreeMap<String, Vec<Extension>>>;
+
+// Extension elements of an iTunes item, for formats
+// without a dedicated field
+pub(crate) fn itunes_to_extensions(value: ITunesItemExtension) -> BTreeMap<String, Vec<Extension>> {
+    let fields = [
+        ("author", value.author),
+        ("block", value.block),
+        ("duration", value.duration),
+        ("explicit", value.explicit),
+        ("isClosedCaptioned", value.closed_captioned),
+        ("order", value.order),
+        ("subtitle", value.subtitle),
+        ("summary", value.summary),
+        ("keywords", value.keywords),
+        ("episode", value.episode),
+        ("season", value.season),
+        ("episodeType", value.episode_type),
+    ];
+    let mut bt: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+    for (key, value) in fields {
+        if let Some(value) = value {
+            bt.insert(key.into(), vec![text_extension("itunes", key, value)]);
+        }
+    }
+    if let Some(href) = value.image {
+        let mut image = text_extension("itunes", "image", String::new());
+        image.value = None;
+        image.attrs.insert("href".into(), href);
+        bt.insert("image".into(), vec![image]);
+    }
+    bt
+}
+
+pub(crate) fn itunes_from_extensions(
+    value: BTreeMap<String, Vec<Extension>>,
+) -> ITunesItemExtension {
+    ITunesItemExtension::from_map(rss_extensions(value))
+}
+
+pub(crate) fn dublin_core_to_extensions(
+    value: DublinCoreExtension,
+) -> BTreeMap<String, Vec<Extension>> {
+    let fields = [
+        ("contributor", value.contributors),
+        ("coverage", value.coverages),
+        ("creator", value.creators),
+        ("date", value.dates),
+        ("description", value.descriptions),
+        ("format", value.formats),
+        ("identifier", value.identifiers),
+        ("language", value.languages),
+        ("publisher", value.publishers),
+        ("relation", value.relations),
+        ("rights", value.rights),
+        ("source", value.sources),
+        ("subject", value.subjects),
+        ("title", value.titles),
+        ("type", value.types),
+    ];
+    let mut bt: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+    for (key, values) in fields {
+        if !values.is_empty() {
+            let values = values
+                .into_iter()
+                .map(|v| text_extension("dc", key, v))
+                .collect();
+            bt.insert(key.into(), values);
+        }
+    }
+    bt
+}
+
+pub(crate) fn dublin_core_from_extensions(
+    value: BTreeMap<String, Vec<Extension>>,
+) -> DublinCoreExtension {
+    DublinCoreExtension::from_map(rss_extensions(value))
+}
+
+fn text_extension(prefix: &str, key: &str, value: String) -> Extension {
+    Extension {
+        name: format!("{}:{}", prefix, key),
+        value: Some(value),
+        ..Default::default()
+    }
+}
+
+fn rss_extensions(value: BTreeMap<String, Vec<Extension>>) -> BTreeMap<String, Vec<RssExtension>> {
+    value
+        .into_iter()
+        .map(|(key, value)| (key, value.into_iter().map(|v| v.into()).collect()))
+        .collect()
+}
+
+// JSON Feed extensions are objects stored under keys starting
+// with an underscore, the key without underscore is the prefix
+pub fn extensions_from_json(value: BTreeMap<String, Value>) -> ExtensionMap {
+    let mut bt1: ExtensionMap = BTreeMap::new();
+    for (key, value) in value.into_iter() {
+        let prefix = match key.strip_prefix('_') {
+            Some(prefix) => prefix,
+            None => continue,
+        };
+        let fields = match value {
+            Value::Object(fields) => fields,
+            _ => continue,
+        };
+        let mut bt2: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+        for (key1, value1) in fields.into_iter() {
+            let name = format!("{}:{}", prefix, key1);
+            bt2.insert(key1, extension_from_json(name, value1));
+        }
+        bt1.insert(prefix.to_string(), bt2);
+    }
+    bt1
+}
+
+pub fn extensions_to_json(value: ExtensionMap) -> BTreeMap<String, Value> {
+    let mut bt: BTreeMap<String, Value> = BTreeMap::new();
+    for (key, value) in value.into_iter() {
+        let mut fields: Map<String, Value> = Map::new();
+        for (key1, value1) in value.into_iter() {
+            fields.insert(key1, extensions_to_json_value(value1));
+        }
+        bt.insert(format!("_{}", key), Value::Object(fields));
+    }
+    bt
+}
+
+// Arrays give several extensions with the same name, scalar
+// values other than strings are kept as their JSON text
+fn extension_from_json(name: String, value: Value) -> Vec<Extension> {
+    match value {
+        Value::Array(values) => values
+            .into_iter()
+            .flat_map(|v| extension_from_json(name.clone(), v))
+            .collect(),
+        Value::Object(fields) => {
+            let mut children: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+            for (key, value) in fields.into_iter() {
+                children.insert(key.clone(), extension_from_json(key, value));
+            }
+            vec![Extension {
+                name,
+                children,
+                ..Default::default()
+            }]
+        }
+        Value::Null => vec![Extension {
+            name,
+            ..Default::default()
+        }],
+        Value::String(s) => vec![Extension {
+            name,
+            value: Some(s),
+            ..Default::default()
+        }],
+        other => vec![Extension {
+            name,
+            value: Some(other.to_string()),
+            ..Default::default()
+        }],
+    }
+}
+
+fn extensions_to_json_value(value: Vec<Extension>) -> Value {
+    let mut values: Vec<Value> = value.into_iter().map(extension_to_json).collect();
+    if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    }
+}
+
+fn extension_to_json(value: Extension) -> Value {
+    if value.attrs.is_empty() && value.children.is_empty() {
+        return match value.value {
+            Some(s) => Value::String(s),
+            None => Value::Null,
+        };
+    }
+    let mut fields: Map<String, Value> = Map::new();
+    for (key, v) in value.attrs.into_iter() {
+        fields.insert(key, Value::String(v));
+    }
+    for (key, v) in value.children.into_iter() {
+        fields.insert(key, extensions_to_json_value(v));
+    }
+    if let Some(v) = value.value {
+        fields.insert("value".into(), Value::String(v));
+    }
+    Value::Object(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_no_loss() {
+        let value: Value = serde_json::from_str(
+            r#"{"_podcast": {"explicit": "no", "owner": {"name": "John"}, "tags": ["a", "b"]}}"#,
+        )
+        .unwrap();
+        let json1: BTreeMap<String, Value> = serde_json::from_value(value).unwrap();
+        let ext: ExtensionMap = extensions_from_json(json1.clone());
+        assert_eq!(ext["podcast"]["tags"].len(), 2);
+        assert_eq!(ext["podcast"]["explicit"][0].name, "podcast:explicit");
+        let json2 = extensions_to_json(ext);
+        assert_eq!(json1, json2);
+    }
+    #[test]
+    fn json_keys_without_underscore_are_ignored() {
+        let mut json: BTreeMap<String, Value> = BTreeMap::new();
+        json.insert("unknown".into(), Value::String("value".into()));
+        assert!(extensions_from_json(json).is_empty());
+    }
+}
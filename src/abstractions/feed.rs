@@ -0,0 +1,1203 @@
+use std::{collections::BTreeMap, io::Write, str::FromStr};
+
+use crate::abstractions::{
+    category::Category,
+    entry::Entry,
+    extension::{extensions_from_json, extensions_to_json, Extension, ExtensionMap},
+    generator::Generator,
+    link::Link,
+    loss::{link_losses, nested, text_losses, Checked},
+    person::{avatar_losses, format_persons, Person, PersonFormat},
+    text::Text,
+};
+use crate::date::parse_date;
+use crate::error::Error;
+use crate::jsonfeed::{Feed as JsonFeed, Hub as JsonHub, VERSION as JSON_VERSION};
+use crate::models::{Feed as ModelFeed, FeedType, WriteConfig};
+use atom_syndication::{
+    extension::{Extension as AtomExtension, ExtensionMap as AtomExtensionMap},
+    Entry as AtomEntry, Feed as AtomFeed, FixedDateTime, Text as AtomText,
+};
+use chrono::{DateTime, FixedOffset, Utc};
+use rss::{
+    extension::dublincore::DublinCoreExtension,
+    extension::itunes::ITunesChannelExtension,
+    extension::syndication::SyndicationExtension,
+    extension::{Extension as RssExtension, ExtensionMap as RssExtensionMap},
+    Channel as RssFeed, Image as RssImage,
+};
+
+// Namespace used to carry extra links in a RSS channel
+pub const ATOM_NAMESPACE: &str = "http://www.w3.org/2005/Atom";
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Feed {
+    pub title: Text,
+    pub id: Option<String>,
+    pub updated: Option<DateTime<FixedOffset>>,
+    pub published: Option<DateTime<FixedOffset>>,
+    pub authors: Vec<Person>,
+    pub contributors: Vec<Person>,
+    pub categories: Vec<Category>,
+    pub generator: Option<Generator>,
+    pub icon: Option<String>,
+    pub logo: Option<String>,
+    pub links: Vec<Link>,
+    pub rights: Option<Text>,
+    pub subtitle: Option<Text>,
+    pub language: Option<String>,
+    pub base: Option<String>,
+    pub ttl: Option<String>,
+    pub skip_hours: Vec<String>,
+    pub skip_days: Vec<String>,
+    pub entries: Vec<Entry>,
+    pub extensions: ExtensionMap,
+    pub namespaces: BTreeMap<String, String>,
+    pub itunes_ext: Option<ITunesChannelExtension>,
+    pub dublin_core_ext: Option<DublinCoreExtension>,
+    pub syndication_ext: Option<SyndicationExtension>,
+}
+
+// Prefix bound to the Atom namespace in a RSS document,
+// "atom" if the document does not declare it, or "atom1"
+// and so on when "atom" is bound to another namespace
+pub(crate) fn atom_prefix(namespaces: &BTreeMap<String, String>) -> String {
+    if let Some((prefix, _)) = namespaces
+        .iter()
+        .find(|(_, ns)| ns.as_str() == ATOM_NAMESPACE)
+    {
+        return prefix.clone();
+    }
+    let mut prefix = String::from("atom");
+    let mut n = 1;
+    while namespaces.contains_key(&prefix) {
+        prefix = format!("atom{}", n);
+        n += 1;
+    }
+    prefix
+}
+
+pub(crate) fn link_from_extension(ext: Extension) -> Option<Link> {
+    let href = ext.attrs.get("href")?.clone();
+    let mut link = Link::from(href);
+    if let Some(rel) = ext.attrs.get("rel") {
+        link.rel = rel.clone();
+    }
+    link.href_lang = ext.attrs.get("hreflang").cloned();
+    link.mime_type = ext.attrs.get("type").cloned();
+    link.title = ext.attrs.get("title").cloned();
+    link.length = ext.attrs.get("length").cloned();
+    Some(link)
+}
+
+pub(crate) fn link_to_extension(link: Link, prefix: &str) -> Extension {
+    let mut attrs: BTreeMap<String, String> = BTreeMap::new();
+    attrs.insert("href".into(), link.href);
+    attrs.insert("rel".into(), link.rel);
+    let optional = [
+        ("hreflang", link.href_lang),
+        ("type", link.mime_type),
+        ("title", link.title),
+        ("length", link.length),
+    ];
+    for (key, value) in optional {
+        if let Some(v) = value {
+            attrs.insert(key.into(), v);
+        }
+    }
+    Extension {
+        name: format!("{}:link", prefix),
+        attrs,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn person_from_extension(ext: Extension) -> Person {
+    let child = |name: &str| {
+        ext.children
+            .get(name)
+            .and_then(|c| c.first())
+            .and_then(|c| c.value.clone())
+    };
+    Person {
+        name: child("name").unwrap_or_default(),
+        email: child("email"),
+        uri: child("uri"),
+    }
+}
+
+// atom:author or atom:contributor element
+pub(crate) fn person_to_extension(person: Person, prefix: &str, local_name: &str) -> Extension {
+    let mut children: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+    let fields = [
+        ("name", Some(person.name)),
+        ("email", person.email),
+        ("uri", person.uri),
+    ];
+    for (key, value) in fields {
+        if let Some(v) = value {
+            children.insert(
+                key.into(),
+                vec![Extension {
+                    name: format!("{}:{}", prefix, key),
+                    value: Some(v),
+                    ..Default::default()
+                }],
+            );
+        }
+    }
+    Extension {
+        name: format!("{}:{}", prefix, local_name),
+        children,
+        ..Default::default()
+    }
+}
+
+impl From<RssFeed> for Feed {
+    fn from(value: RssFeed) -> Self {
+        let mut extensions: ExtensionMap = {
+            let mut bt1: BTreeMap<String, BTreeMap<String, Vec<Extension>>> = BTreeMap::new();
+            for (key, value) in value.extensions.into_iter() {
+                let mut bt2: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+                for (key1, value1) in value.into_iter() {
+                    let mut ve: Vec<Extension> = Vec::new();
+                    for v in value1 {
+                        ve.push(v.into());
+                    }
+                    bt2.insert(key1, ve);
+                }
+                bt1.insert(key, bt2);
+            }
+            bt1
+        };
+        let mut links: Vec<Link> = if value.link.is_empty() {
+            vec![]
+        } else {
+            vec![value.link.into()]
+        };
+        // atom:link elements (self, hub, next...) are
+        // promoted to first class links
+        let prefix = atom_prefix(&value.namespaces);
+        if let Some(atom) = extensions.get_mut(&prefix) {
+            if let Some(atom_links) = atom.remove("link") {
+                links.extend(atom_links.into_iter().filter_map(link_from_extension));
+            }
+            if atom.is_empty() {
+                extensions.remove(&prefix);
+            }
+        }
+        let title: Text = value.title.into();
+        let subtitle: Option<Text> = if value.description.is_empty() {
+            None
+        } else {
+            Some(value.description.into())
+        };
+        let mut authors: Vec<Person> = match value.managing_editor {
+            Some(text) => Person::parse_list(&text),
+            None => vec![],
+        };
+        let published: Option<DateTime<FixedOffset>> =
+            value.pub_date.as_deref().and_then(parse_date);
+        let mut updated: Option<DateTime<FixedOffset>> =
+            value.last_build_date.as_deref().and_then(parse_date);
+        let mut categories: Vec<Category> =
+            value.categories.into_iter().map(|s| s.into()).collect();
+        let mut language = value.language;
+        let mut rights: Option<Text> = value.copyright.map(|s| s.into());
+        // RSS 1.0 channels carry their metadata with Dublin
+        // Core, use it when the RSS field is missing
+        let mut dublin_core_ext = value.dublin_core_ext;
+        if let Some(dc) = dublin_core_ext.as_mut() {
+            if authors.is_empty() {
+                authors = dc.creators.drain(..).map(|s| s.into()).collect();
+            }
+            if updated.is_none() {
+                updated = dc.dates.first().and_then(|s| parse_date(s));
+                if updated.is_some() {
+                    dc.dates.remove(0);
+                }
+            }
+            if categories.is_empty() {
+                categories = dc.subjects.drain(..).map(|s| s.into()).collect();
+            }
+            if language.is_none() && !dc.languages.is_empty() {
+                language = Some(dc.languages.remove(0));
+            }
+            if rights.is_none() && !dc.rights.is_empty() {
+                rights = Some(dc.rights.remove(0).into());
+            }
+        }
+        if dublin_core_ext == Some(DublinCoreExtension::default()) {
+            dublin_core_ext = None;
+        }
+        Self {
+            title,
+            // no equivalent field on RSS channel
+            id: None,
+            updated,
+            published,
+            authors,
+            contributors: vec![],
+            categories,
+            generator: value.generator.map(|s| s.into()),
+            icon: None,
+            logo: value.image.map(|s| s.url),
+            links,
+            rights,
+            subtitle,
+            language,
+            base: None,
+            ttl: value.ttl,
+            skip_hours: value.skip_hours,
+            skip_days: value.skip_days,
+            entries: value
+                .items
+                .into_iter()
+                .map(|s| Entry::from_rss_with_prefix(s, &prefix))
+                .collect(),
+            extensions,
+            namespaces: value.namespaces,
+            itunes_ext: value.itunes_ext,
+            dublin_core_ext,
+            syndication_ext: value.syndication_ext,
+        }
+    }
+}
+
+impl From<Feed> for RssFeed {
+    fn from(value: Feed) -> Self {
+        let last_build_date: Option<String> = value.updated_date().map(|s| s.to_rfc2822());
+        let mut namespaces = value.namespaces;
+        let prefix = atom_prefix(&namespaces);
+        // The first alternate link is the channel link, any
+        // other link is written as an atom:link element
+        let mut link: String = "".into();
+        let mut atom_links: Vec<Extension> = Vec::new();
+        for l in value.links {
+            if link.is_empty() && l.rel == "alternate" {
+                link = l.into();
+            } else {
+                atom_links.push(link_to_extension(l, &prefix));
+            }
+        }
+        let mut ext = value.extensions;
+        if !atom_links.is_empty() {
+            namespaces.insert(prefix.clone(), ATOM_NAMESPACE.into());
+            ext.entry(prefix)
+                .or_default()
+                .entry("link".into())
+                .or_default()
+                .extend(atom_links);
+        }
+        let extensions: RssExtensionMap = {
+            let mut bt1: BTreeMap<String, BTreeMap<String, Vec<RssExtension>>> = BTreeMap::new();
+            for (key, value) in ext.into_iter() {
+                let mut bt2: BTreeMap<String, Vec<RssExtension>> = BTreeMap::new();
+                for (key1, value1) in value.into_iter() {
+                    let mut ve: Vec<RssExtension> = Vec::new();
+                    for v in value1 {
+                        ve.push(v.into());
+                    }
+                    bt2.insert(key1, ve);
+                }
+                bt1.insert(key, bt2);
+            }
+            bt1
+        };
+        let title: String = value.title.into();
+        let managing_editor: Option<String> =
+            format_persons(&value.authors, PersonFormat::default());
+        // RSS requires a title and a link for the image,
+        // reuse the ones of the channel
+        let image: Option<RssImage> = value.logo.map(|url| RssImage {
+            url,
+            title: title.clone(),
+            link: link.clone(),
+            ..Default::default()
+        });
+        Self {
+            title,
+            link,
+            description: value.subtitle.map(|s| s.into()).unwrap_or_default(),
+            language: value.language,
+            copyright: value.rights.map(|s| s.into()),
+            managing_editor,
+            pub_date: value.published.map(|s| s.to_rfc2822()),
+            last_build_date,
+            categories: value.categories.into_iter().map(|s| s.into()).collect(),
+            generator: value.generator.map(|s| s.into()),
+            ttl: value.ttl,
+            image,
+            skip_hours: value.skip_hours,
+            skip_days: value.skip_days,
+            items: value.entries.into_iter().map(|s| s.into()).collect(),
+            extensions,
+            itunes_ext: value.itunes_ext,
+            dublin_core_ext: value.dublin_core_ext,
+            syndication_ext: value.syndication_ext,
+            namespaces,
+            // webMaster, docs, cloud, rating and textInput
+            // have no abstract equivalent
+            ..Default::default()
+        }
+    }
+}
+
+impl From<AtomFeed> for Feed {
+    fn from(value: AtomFeed) -> Self {
+        let title: Text = value.title.into();
+        // Only need to store the value if it is
+        // different from empty string
+        let id: Option<String> = if value.id.is_empty() {
+            None
+        } else {
+            Some(value.id)
+        };
+        let extensions: ExtensionMap = {
+            let mut bt1: BTreeMap<String, BTreeMap<String, Vec<Extension>>> = BTreeMap::new();
+            for (key, value) in value.extensions.into_iter() {
+                let mut bt2: BTreeMap<String, Vec<Extension>> = BTreeMap::new();
+                for (key1, value1) in value.into_iter() {
+                    let mut ve: Vec<Extension> = Vec::new();
+                    for v in value1 {
+                        ve.push(v.into());
+                    }
+                    bt2.insert(key1, ve);
+                }
+                bt1.insert(key, bt2);
+            }
+            bt1
+        };
+        let mut feed = Self {
+            title,
+            id,
+            updated: Some(value.updated),
+            // no equivalent field on Atom feed
+            published: None,
+            authors: value.authors.into_iter().map(|s| s.into()).collect(),
+            contributors: value.contributors.into_iter().map(|s| s.into()).collect(),
+            categories: value.categories.into_iter().map(|s| s.into()).collect(),
+            generator: value.generator.map(|s| s.into()),
+            icon: value.icon,
+            logo: value.logo,
+            links: value.links.into_iter().map(|s| s.into()).collect(),
+            rights: value.rights.map(|s| s.into()),
+            subtitle: value.subtitle.map(|s| s.into()),
+            language: value.lang,
+            base: value.base,
+            ttl: None,
+            skip_hours: vec![],
+            skip_days: vec![],
+            entries: value.entries.into_iter().map(|s| s.into()).collect(),
+            extensions,
+            namespaces: value.namespaces,
+            itunes_ext: None,
+            dublin_core_ext: None,
+            syndication_ext: None,
+        };
+        feed.inherit_authors();
+        feed
+    }
+}
+
+impl From<Feed> for AtomFeed {
+    fn from(mut value: Feed) -> Self {
+        value.omit_inherited_authors();
+        let title: AtomText = value.title.into();
+        let updated: FixedDateTime = value.updated.unwrap_or_default();
+        let extensions: AtomExtensionMap = {
+            let mut bt1: BTreeMap<String, BTreeMap<String, Vec<AtomExtension>>> = BTreeMap::new();
+            for (key, value) in value.extensions.into_iter() {
+                let mut bt2: BTreeMap<String, Vec<AtomExtension>> = BTreeMap::new();
+                for (key1, value1) in value.into_iter() {
+                    let mut ve: Vec<AtomExtension> = Vec::new();
+                    for v in value1 {
+                        ve.push(v.into());
+                    }
+                    bt2.insert(key1, ve);
+                }
+                bt1.insert(key, bt2);
+            }
+            bt1
+        };
+        let mut namespaces = value.namespaces;
+        let mut entries: Vec<AtomEntry> = value.entries.into_iter().map(|s| s.into()).collect();
+        // Declare the extension namespaces of entries once on the feed
+        for entry in entries.iter_mut() {
+            entry
+                .namespaces
+                .retain(|prefix, uri| match namespaces.get(prefix) {
+                    Some(declared) => declared != uri,
+                    None => {
+                        namespaces.insert(prefix.clone(), uri.clone());
+                        false
+                    }
+                });
+        }
+        Self {
+            title,
+            id: value.id.unwrap_or_default(),
+            updated,
+            authors: value.authors.into_iter().map(|s| s.into()).collect(),
+            categories: value.categories.into_iter().map(|s| s.into()).collect(),
+            contributors: value.contributors.into_iter().map(|s| s.into()).collect(),
+            generator: value.generator.map(|s| s.into()),
+            icon: value.icon,
+            links: value.links.into_iter().map(|s| s.into()).collect(),
+            logo: value.logo,
+            rights: value.rights.map(|s| s.into()),
+            subtitle: value.subtitle.map(|s| s.into()),
+            entries,
+            extensions,
+            namespaces,
+            base: value.base,
+            lang: value.language,
+        }
+    }
+}
+
+impl From<JsonFeed> for Feed {
+    fn from(value: JsonFeed) -> Self {
+        let mut links: Vec<Link> = Vec::new();
+        let rel_links = [
+            ("alternate", value.home_page_url),
+            ("self", value.feed_url),
+            ("next", value.next_url),
+        ];
+        for (rel, href) in rel_links {
+            if let Some(href) = href {
+                links.push(Link {
+                    href,
+                    rel: rel.into(),
+                    ..Default::default()
+                });
+            }
+        }
+        // The hub type (WebSub, rssCloud...) is kept as link title
+        links.extend(value.hubs.into_iter().map(|s| Link {
+            href: s.url,
+            rel: "hub".into(),
+            title: Some(s.hub_type),
+            ..Default::default()
+        }));
+        let mut authors: Vec<Person> = value.authors.into_iter().map(|s| s.into()).collect();
+        if authors.is_empty() {
+            authors.extend(value.author.map(|s| s.into()));
+        }
+        let mut feed = Self {
+            title: value.title.into(),
+            id: None,
+            updated: None,
+            published: None,
+            authors,
+            contributors: vec![],
+            categories: vec![],
+            generator: None,
+            // JSON icon is the large image, favicon the small one
+            icon: value.favicon,
+            logo: value.icon,
+            links,
+            rights: None,
+            subtitle: value.description.map(|s| s.into()),
+            language: value.language,
+            base: None,
+            ttl: None,
+            skip_hours: vec![],
+            skip_days: vec![],
+            entries: value.items.into_iter().map(|s| s.into()).collect(),
+            extensions: extensions_from_json(value.extensions),
+            namespaces: BTreeMap::new(),
+            itunes_ext: None,
+            dublin_core_ext: None,
+            syndication_ext: None,
+        };
+        feed.inherit_authors();
+        feed
+    }
+}
+
+impl From<Feed> for JsonFeed {
+    fn from(mut value: Feed) -> Self {
+        value.omit_inherited_authors();
+        let (mut home_page_url, mut feed_url, mut next_url) = (None, None, None);
+        let mut hubs: Vec<JsonHub> = Vec::new();
+        for link in value.links {
+            match link.rel.as_str() {
+                "alternate" if home_page_url.is_none() => home_page_url = Some(link.href),
+                "self" if feed_url.is_none() => feed_url = Some(link.href),
+                "next" if next_url.is_none() => next_url = Some(link.href),
+                "hub" => hubs.push(JsonHub {
+                    hub_type: link.title.unwrap_or_else(|| "WebSub".into()),
+                    url: link.href,
+                }),
+                _ => {}
+            }
+        }
+        Self {
+            version: JSON_VERSION.into(),
+            title: value.title.into(),
+            home_page_url,
+            feed_url,
+            description: value.subtitle.map(|s| s.into()),
+            next_url,
+            icon: value.logo,
+            favicon: value.icon,
+            authors: value.authors.into_iter().map(|s| s.into()).collect(),
+            language: value.language,
+            hubs,
+            items: value.entries.into_iter().map(|s| s.into()).collect(),
+            extensions: extensions_to_json(value.extensions),
+            ..Default::default()
+        }
+    }
+}
+
+impl Feed {
+    // The update date, without the placeholder the default
+    // feed carries for Atom
+    pub fn updated_date(&self) -> Option<DateTime<FixedOffset>> {
+        self.updated.filter(|u| *u != DateTime::<Utc>::UNIX_EPOCH)
+    }
+
+    // Same as the From conversion, but the Atom-only fields
+    // of the entries are kept in extensions, see
+    // Entry::into_rss_preserving
+    pub fn into_rss_preserving(self) -> RssFeed {
+        let mut feed = self;
+        let entries = std::mem::take(&mut feed.entries);
+        let prefix = atom_prefix(&feed.namespaces);
+        let mut channel: RssFeed = feed.into();
+        channel.items = entries
+            .into_iter()
+            .map(|e| e.into_rss_preserving_with_prefix(&prefix))
+            .collect();
+        if channel
+            .items
+            .iter()
+            .any(|i| i.extensions.contains_key(&prefix))
+        {
+            channel.namespaces.insert(prefix, ATOM_NAMESPACE.into());
+        }
+        channel
+    }
+
+    // Entries without authors are written by the authors of
+    // their source, or else of the feed. Atom and JSON Feed
+    // entries get them when read, RSS has no such rule.
+    pub fn inherit_authors(&mut self) {
+        for entry in self.entries.iter_mut() {
+            if entry.authors.is_empty() {
+                entry.authors = inherited_authors(entry, &self.authors).to_vec();
+            }
+        }
+    }
+
+    // Leave inherited authors implicit when writing Atom
+    // and JSON Feed
+    fn omit_inherited_authors(&mut self) {
+        for entry in self.entries.iter_mut() {
+            if entry.authors == inherited_authors(entry, &self.authors) {
+                entry.authors.clear();
+            }
+        }
+    }
+
+    // Same as the From conversion with the author and
+    // managingEditor fields written in the given format
+    pub fn into_rss_with_format(self, format: PersonFormat) -> RssFeed {
+        let mut feed = self;
+        let entries = std::mem::take(&mut feed.entries);
+        let managing_editor = format_persons(&feed.authors, format);
+        let mut channel: RssFeed = feed.into();
+        channel.managing_editor = managing_editor;
+        channel.items = entries
+            .into_iter()
+            .map(|e| e.into_rss_with_format(format))
+            .collect();
+        channel
+    }
+
+    pub fn to_rss_checked(&self) -> Checked<RssFeed> {
+        let mut lost: Vec<String> = text_losses("title", &self.title);
+        if self.id.is_some() {
+            lost.push("id".into());
+        }
+        if !self.contributors.is_empty() {
+            lost.push("contributors".into());
+        }
+        for (i, person) in self.authors.iter().enumerate() {
+            if person.uri.is_some() {
+                lost.push(format!("authors[{}].uri", i));
+            }
+        }
+        for (i, category) in self.categories.iter().enumerate() {
+            if category.label.is_some() {
+                lost.push(format!("categories[{}].label", i));
+            }
+        }
+        if let Some(generator) = &self.generator {
+            if generator.uri.is_some() {
+                lost.push("generator.uri".into());
+            }
+            if generator.version.is_some() {
+                lost.push("generator.version".into());
+            }
+        }
+        if self.icon.is_some() {
+            lost.push("icon".into());
+        }
+        // only the channel link is reduced to its href, the
+        // other links are written as atom:link elements
+        if let Some(i) = self.links.iter().position(|l| l.rel == "alternate") {
+            lost.extend(link_losses(&format!("links[{}]", i), &self.links[i]));
+        }
+        if let Some(rights) = &self.rights {
+            lost.extend(text_losses("rights", rights));
+        }
+        if let Some(subtitle) = &self.subtitle {
+            lost.extend(text_losses("subtitle", subtitle));
+        }
+        if self.base.is_some() {
+            lost.push("base".into());
+        }
+        for (i, entry) in self.entries.iter().enumerate() {
+            lost.extend(nested(&format!("entries[{}]", i), entry.rss_losses()));
+        }
+        Checked {
+            value: self.clone().into(),
+            lost,
+        }
+    }
+
+    pub fn to_atom_checked(&self) -> Checked<AtomFeed> {
+        let mut lost: Vec<String> = Vec::new();
+        if self.published.is_some() {
+            lost.push("published".into());
+        }
+        if self.ttl.is_some() {
+            lost.push("ttl".into());
+        }
+        if !self.skip_hours.is_empty() {
+            lost.push("skip_hours".into());
+        }
+        if !self.skip_days.is_empty() {
+            lost.push("skip_days".into());
+        }
+        if self.itunes_ext.is_some() {
+            lost.push("itunes_ext".into());
+        }
+        if self.dublin_core_ext.is_some() {
+            lost.push("dublin_core_ext".into());
+        }
+        if self.syndication_ext.is_some() {
+            lost.push("syndication_ext".into());
+        }
+        for (i, entry) in self.entries.iter().enumerate() {
+            lost.extend(nested(&format!("entries[{}]", i), entry.atom_losses()));
+        }
+        Checked {
+            value: self.clone().into(),
+            lost,
+        }
+    }
+
+    // Same as the From conversion, with the fields of the
+    // JSON document the abstraction has nowhere to put
+    pub fn from_json_checked(feed: &JsonFeed) -> Checked<Feed> {
+        let mut lost: Vec<String> = avatar_losses(&feed.authors, feed.author.as_ref());
+        if feed.user_comment.is_some() {
+            lost.push("user_comment".into());
+        }
+        if feed.expired.is_some() {
+            lost.push("expired".into());
+        }
+        for (i, item) in feed.items.iter().enumerate() {
+            lost.extend(nested(&format!("items[{}]", i), Entry::json_losses(item)));
+        }
+        Checked {
+            value: feed.clone().into(),
+            lost,
+        }
+    }
+
+    pub fn write_to<W: Write>(&self, writer: W, ftype: FeedType) -> Result<W, Error> {
+        self.write_with_config(writer, ftype, &WriteConfig::default())
+    }
+
+    pub fn write_with_config<W: Write>(
+        &self,
+        writer: W,
+        ftype: FeedType,
+        config: &WriteConfig,
+    ) -> Result<W, Error> {
+        ModelFeed::from_abstract(self.clone(), ftype)?.write_with_config(
+            writer,
+            FeedType::Auto,
+            config,
+        )
+    }
+}
+
+fn inherited_authors<'a>(entry: &'a Entry, feed_authors: &'a [Person]) -> &'a [Person] {
+    match entry.source.as_ref().filter(|s| !s.authors.is_empty()) {
+        Some(source) => &source.authors,
+        None => feed_authors,
+    }
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        Feed {
+            title: Text::default(),
+            id: Option::default(),
+            // At worst set it at epoch time
+            updated: Some(DateTime::<FixedOffset>::from_str("1970-01-01T00:00:00Z").unwrap()),
+            published: Option::default(),
+            authors: Vec::default(),
+            contributors: Vec::default(),
+            categories: Vec::default(),
+            generator: Option::default(),
+            icon: Option::default(),
+            logo: Option::default(),
+            links: Vec::default(),
+            rights: Option::default(),
+            subtitle: Option::default(),
+            language: Option::default(),
+            base: Option::default(),
+            ttl: Option::default(),
+            skip_hours: Vec::default(),
+            skip_days: Vec::default(),
+            entries: Vec::default(),
+            extensions: BTreeMap::default(),
+            namespaces: BTreeMap::default(),
+            itunes_ext: Option::default(),
+            dublin_core_ext: Option::default(),
+            syndication_ext: Option::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use super::*;
+    use rss::{Category as RssCategory, Item as RssEntry};
+
+    pub(crate) fn new_feed() -> Feed {
+        Feed {
+            title: crate::abstractions::text::tests::new_text(),
+            id: Some("id".into()),
+            updated: Some(DateTime::default()),
+            authors: vec![crate::abstractions::person::tests::new_person()],
+            contributors: vec![crate::abstractions::person::tests::new_person()],
+            categories: vec![crate::abstractions::category::tests::new_category()],
+            generator: Some(crate::abstractions::generator::tests::new_generator()),
+            icon: Some("Icon".into()),
+            logo: Some("Logo".into()),
+            links: vec![crate::abstractions::link::tests::new_link()],
+            rights: Some(crate::abstractions::text::tests::new_text()),
+            subtitle: Some(crate::abstractions::text::tests::new_text()),
+            language: Some("Lang".into()),
+            base: Some("Base".into()),
+            entries: vec![Entry::default()],
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn new_atom_feed() -> AtomFeed {
+        AtomFeed {
+            title: crate::abstractions::text::tests::new_atom_text(),
+            id: "id".into(),
+            updated: DateTime::default(),
+            authors: vec![crate::abstractions::person::tests::new_atom_person()],
+            categories: vec![crate::abstractions::category::tests::new_atom_category()],
+            contributors: vec![crate::abstractions::person::tests::new_atom_person()],
+            generator: Some(crate::abstractions::generator::tests::new_atom_generator()),
+            icon: Some("Icon".into()),
+            links: vec![crate::abstractions::link::tests::new_atom_link()],
+            logo: Some("Logo".into()),
+            rights: Some(crate::abstractions::text::tests::new_atom_text()),
+            subtitle: Some(crate::abstractions::text::tests::new_atom_text()),
+            entries: vec![AtomEntry::default()],
+            base: Some("Base".into()),
+            lang: Some("Lang".into()),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn new_rss_feed() -> RssFeed {
+        let mut attrs: BTreeMap<String, String> = BTreeMap::new();
+        attrs.insert("href".into(), "https://example.org/rss.xml".into());
+        attrs.insert("rel".into(), "self".into());
+        let atom_link = RssExtension {
+            name: "atom:link".into(),
+            attrs,
+            ..Default::default()
+        };
+        let mut extensions: RssExtensionMap = BTreeMap::new();
+        extensions
+            .entry("atom".into())
+            .or_default()
+            .insert("link".into(), vec![atom_link]);
+        let mut namespaces: BTreeMap<String, String> = BTreeMap::new();
+        namespaces.insert("atom".into(), ATOM_NAMESPACE.into());
+        RssFeed {
+            title: "Title".into(),
+            link: "https://example.org".into(),
+            description: "Description".into(),
+            language: Some("en".into()),
+            copyright: Some("Copyright".into()),
+            managing_editor: Some("John".into()),
+            pub_date: Some("Mon, 1 Jan 2024 00:00:00 +0000".into()),
+            last_build_date: Some("Tue, 2 Jan 2024 00:00:00 +0000".into()),
+            categories: vec![RssCategory {
+                name: "Category".into(),
+                domain: None,
+            }],
+            generator: Some("Generator".into()),
+            ttl: Some("60".into()),
+            image: Some(RssImage {
+                url: "https://example.org/logo.png".into(),
+                title: "Title".into(),
+                link: "https://example.org".into(),
+                ..Default::default()
+            }),
+            skip_hours: vec!["0".into()],
+            skip_days: vec!["Sunday".into()],
+            extensions,
+            namespaces,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_abstract_to_atom_equal() {
+        let feed1: AtomFeed = Feed::default().into();
+        let feed2 = AtomFeed::default();
+        assert_eq!(feed1, feed2);
+    }
+    #[test]
+    fn default_atom_to_abstract_equal() {
+        let feed1: Feed = AtomFeed::default().into();
+        let feed2 = Feed::default();
+        assert_eq!(feed1, feed2);
+    }
+    #[test]
+    fn abstract_to_atom_equal() {
+        let feed1: AtomFeed = new_feed().into();
+        let feed2 = new_atom_feed();
+        assert_eq!(feed1, feed2);
+    }
+    #[test]
+    fn atom_to_abstract_equal() {
+        let feed1: Feed = new_atom_feed().into();
+        let mut feed2 = new_feed();
+        // the entry is written by the feed authors
+        feed2.entries[0].authors = feed2.authors.clone();
+        assert_eq!(feed1, feed2);
+    }
+    #[test]
+    fn atom_no_loss() {
+        let feed1: AtomFeed = new_atom_feed();
+        let feed2: Feed = feed1.clone().into();
+        let feed2: AtomFeed = feed2.into();
+        assert_eq!(feed1, feed2);
+    }
+    #[test]
+    fn rss_no_loss() {
+        let feed1: RssFeed = new_rss_feed();
+        let feed2: Feed = feed1.clone().into();
+        let feed2: RssFeed = feed2.into();
+        assert_eq!(feed1, feed2);
+    }
+    #[test]
+    fn rss_atom_links_are_promoted() {
+        let feed: Feed = new_rss_feed().into();
+        assert_eq!(feed.links.len(), 2);
+        assert_eq!(feed.links[0].href, "https://example.org");
+        assert_eq!(feed.links[1].rel, "self");
+        assert!(feed.extensions.is_empty());
+    }
+    #[test]
+    fn json_no_loss() {
+        let feed1 = JsonFeed {
+            title: "Title".into(),
+            home_page_url: Some("https://example.org/".into()),
+            feed_url: Some("https://example.org/feed.json".into()),
+            next_url: Some("https://example.org/feed.json?page=2".into()),
+            description: Some("Description".into()),
+            icon: Some("https://example.org/icon.png".into()),
+            favicon: Some("https://example.org/favicon.ico".into()),
+            authors: vec![crate::abstractions::person::tests::new_json_person()],
+            language: Some("en".into()),
+            hubs: vec![JsonHub {
+                hub_type: "WebSub".into(),
+                url: "https://hub.example.org".into(),
+            }],
+            ..Default::default()
+        };
+        let feed2: Feed = feed1.clone().into();
+        assert_eq!(feed2.links.len(), 4);
+        let feed2: JsonFeed = feed2.into();
+        assert_eq!(feed1, feed2);
+    }
+    #[test]
+    fn default_date_not_written() {
+        let channel: RssFeed = Feed::default().into();
+        assert_eq!(channel.last_build_date, None);
+        let feed = Feed {
+            updated: Some(DateTime::<FixedOffset>::from_str("2024-01-02T00:00:00Z").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(feed.updated_date(), feed.updated);
+        let channel: RssFeed = feed.into();
+        assert_eq!(
+            channel.last_build_date.as_deref(),
+            Some("Tue, 2 Jan 2024 00:00:00 +0000")
+        );
+    }
+    #[test]
+    fn json_losses() {
+        let feed = JsonFeed {
+            title: "Title".into(),
+            author: Some(crate::jsonfeed::Author {
+                name: Some("Jane".into()),
+                avatar: Some("https://example.org/jane.png".into()),
+                ..Default::default()
+            }),
+            expired: Some(true),
+            items: vec![crate::jsonfeed::Item {
+                id: "1".into(),
+                content_html: Some("<p>Content</p>".into()),
+                content_text: Some("Content".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let checked = Feed::from_json_checked(&feed);
+        assert_eq!(
+            checked.lost,
+            vec!["author.avatar", "expired", "items[0].content_text"]
+        );
+        assert_eq!(checked.value.entries.len(), 1);
+    }
+    #[test]
+    fn atom_rss_atom_preserving() {
+        let data = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>T</title><id>urn:feed</id>
+<updated>2024-01-02T00:00:00Z</updated>
+<entry><title>E</title><id>urn:entry</id>
+<updated>2024-01-03T10:00:00+01:00</updated>
+<published>2024-01-01T10:00:00+01:00</published>
+<author><name>Jane</name><email>jane@example.org</email><uri>https://example.org/jane</uri></author>
+<contributor><name>John</name><uri>https://example.org/john</uri></contributor>
+<rights type="html">&lt;b&gt;CC&lt;/b&gt;</rights>
+<link rel="alternate" type="text/html" href="https://example.org/e"/>
+<link rel="related" href="https://example.org/r"/>
+</entry></feed>"#;
+        let atom_feed = AtomFeed::read_from(data.as_bytes()).unwrap();
+        let feed1: Feed = atom_feed.into();
+        let channel = feed1.clone().into_rss_preserving();
+        let buf = channel.write_to(Vec::new()).unwrap();
+        let channel = RssFeed::read_from(&buf[..]).unwrap();
+        let feed2: Feed = channel.into();
+        let atom1: AtomEntry = feed1.entries[0].clone().into();
+        let atom2: AtomEntry = feed2.entries[0].clone().into();
+        assert_eq!(atom1, atom2);
+    }
+    #[test]
+    fn rss_preserving_namespace_prefix() {
+        let entry = Entry {
+            title: "E".to_string().into(),
+            updated: Some(DateTime::<FixedOffset>::from_str("2024-01-03T10:00:00Z").unwrap()),
+            contributors: vec!["Paul".to_string().into()],
+            links: vec![
+                "https://example.org/e".to_string().into(),
+                "https://example.org/r".to_string().into(),
+            ],
+            ..Default::default()
+        };
+        for (namespaces, prefix) in [
+            (vec![("a", ATOM_NAMESPACE)], "a"),
+            (vec![("atom", "urn:other")], "atom1"),
+        ] {
+            let feed1 = Feed {
+                title: "T".to_string().into(),
+                entries: vec![entry.clone()],
+                namespaces: namespaces
+                    .iter()
+                    .map(|(p, ns)| (p.to_string(), ns.to_string()))
+                    .collect(),
+                ..Default::default()
+            };
+            let channel = feed1.into_rss_preserving();
+            assert_eq!(channel.namespaces[prefix], ATOM_NAMESPACE);
+            for (p, ns) in &namespaces {
+                assert_eq!(channel.namespaces[*p], *ns);
+            }
+            if prefix == "a" {
+                assert!(!channel.namespaces.contains_key("atom"));
+            }
+            assert!(channel.items[0].extensions.contains_key(prefix));
+            assert_eq!(
+                channel.items[0]
+                    .dublin_core_ext
+                    .as_ref()
+                    .unwrap()
+                    .contributors,
+                vec!["Paul".to_string()]
+            );
+            let buf = channel.write_to(Vec::new()).unwrap();
+            let channel = RssFeed::read_from(&buf[..]).unwrap();
+            let feed2: Feed = channel.into();
+            assert_eq!(feed2.entries[0], entry, "{}", prefix);
+        }
+    }
+    #[test]
+    fn rss_atom_rss_podcast() {
+        let data = r#"<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd" xmlns:dc="http://purl.org/dc/elements/1.1/">
+<channel><title>Podcast</title><link>https://example.org</link><description>D</description>
+<item><title>Episode 1</title><guid>https://example.org/1</guid>
+<comments>https://example.org/1#comments</comments>
+<enclosure url="https://example.org/1.mp3" length="12345" type="audio/mpeg"/>
+<itunes:duration>01:02:03</itunes:duration>
+<itunes:explicit>false</itunes:explicit>
+<itunes:image href="https://example.org/1.jpg"/>
+<itunes:episode>1</itunes:episode>
+<dc:creator>Jane</dc:creator>
+<dc:subject>Rust</dc:subject>
+<dc:subject>Feeds</dc:subject>
+</item></channel></rss>"#;
+        let channel = RssFeed::read_from(data.as_bytes()).unwrap();
+        let feed1: Feed = channel.into();
+        let atom_feed: AtomFeed = feed1.clone().into();
+        assert!(atom_feed.namespaces.contains_key("itunes"));
+        assert!(atom_feed.entries[0].namespaces.is_empty());
+        let buf = atom_feed.write_to(Vec::new()).unwrap();
+        let atom_feed = AtomFeed::read_from(&buf[..]).unwrap();
+        let feed2: Feed = atom_feed.into();
+        let item1: RssEntry = feed1.entries[0].clone().into();
+        let item2: RssEntry = feed2.entries[0].clone().into();
+        assert_eq!(item1.enclosure, item2.enclosure);
+        assert_eq!(item1.comments, item2.comments);
+        assert_eq!(item1.itunes_ext, item2.itunes_ext);
+        assert_eq!(item1.dublin_core_ext, item2.dublin_core_ext);
+        assert_eq!(
+            item2.itunes_ext.unwrap().image.as_deref(),
+            Some("https://example.org/1.jpg")
+        );
+    }
+    #[test]
+    fn atom_authors_inherited() {
+        let data = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>T</title><id>urn:feed</id>
+<updated>2024-01-02T00:00:00Z</updated><author><name>Jane</name></author>
+<entry><title>A</title><id>urn:a</id><updated>2024-01-02T00:00:00Z</updated></entry>
+<entry><title>B</title><id>urn:b</id><updated>2024-01-02T00:00:00Z</updated>
+<author><name>John</name></author><contributor><name>Paul</name></contributor></entry>
+<entry><title>C</title><id>urn:c</id><updated>2024-01-02T00:00:00Z</updated>
+<source><author><name>George</name></author></source></entry>
+</feed>"#;
+        let atom_feed1 = AtomFeed::read_from(data.as_bytes()).unwrap();
+        let feed: Feed = atom_feed1.clone().into();
+        let authors: Vec<&str> = feed
+            .entries
+            .iter()
+            .map(|e| e.authors[0].name.as_str())
+            .collect();
+        assert_eq!(authors, vec!["Jane", "John", "George"]);
+        assert_eq!(feed.entries[1].contributors[0].name, "Paul");
+        let channel: RssFeed = feed.clone().into();
+        assert_eq!(channel.items[0].author.as_deref(), Some("Jane"));
+        let atom_feed2: AtomFeed = feed.into();
+        assert_eq!(atom_feed1.entries, atom_feed2.entries);
+    }
+    #[test]
+    fn abstract_to_rss_losses() {
+        let mut feed = new_feed();
+        feed.entries[0].rights = Some(crate::abstractions::text::tests::new_text());
+        let checked = feed.to_rss_checked();
+        assert_eq!(
+            checked.lost,
+            vec![
+                "title.base",
+                "title.lang",
+                "title.text_type",
+                "id",
+                "contributors",
+                "authors[0].uri",
+                "categories[0].label",
+                "generator.uri",
+                "generator.version",
+                "icon",
+                "rights.base",
+                "rights.lang",
+                "rights.text_type",
+                "subtitle.base",
+                "subtitle.lang",
+                "subtitle.text_type",
+                "base",
+                "entries[0].rights"
+            ]
+        );
+        assert_eq!(checked.value.items.len(), 1);
+    }
+    #[test]
+    fn abstract_to_rss_generator_losses() {
+        let mut feed = Feed {
+            generator: Some(Generator {
+                value: "Hugo".into(),
+                uri: None,
+                version: None,
+            }),
+            ..Default::default()
+        };
+        assert!(feed.to_rss_checked().is_lossless());
+        feed.generator = Some(Generator {
+            value: "Hugo".into(),
+            uri: Some("https://gohugo.io/".into()),
+            version: Some("0.120".into()),
+        });
+        assert_eq!(
+            feed.to_rss_checked().lost,
+            vec!["generator.uri", "generator.version"]
+        );
+    }
+    #[test]
+    fn abstract_to_rss_channel_link_losses() {
+        let feed = Feed {
+            links: vec![
+                Link {
+                    href: "https://example.org/feed".into(),
+                    rel: "self".into(),
+                    mime_type: Some("application/rss+xml".into()),
+                    ..Default::default()
+                },
+                Link {
+                    href: "https://example.org/".into(),
+                    href_lang: Some("en".into()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(feed.to_rss_checked().lost, vec!["links[1].href_lang"]);
+    }
+    #[test]
+    fn abstract_to_atom_losses() {
+        let checked = new_feed().to_atom_checked();
+        assert!(checked.is_lossless());
+        let rss_feed: Feed = new_rss_feed().into();
+        let checked = rss_feed.to_atom_checked();
+        assert!(checked.lost.contains(&"ttl".to_string()));
+        assert!(checked.strict().is_err());
+    }
+    #[test]
+    fn rdf_to_abstract() {
+        let rdf = crate::rdf::read_from(crate::rdf::tests::FEED.as_bytes()).unwrap();
+        let feed: Feed = rdf.into();
+        assert_eq!(feed.language, Some("en".into()));
+        assert_eq!(feed.dublin_core_ext, None);
+        assert_eq!(feed.entries[0].authors[0].name, "John");
+        assert_eq!(feed.entries[0].guid.value, "http://example.org/1");
+    }
+}
@@ -0,0 +1,233 @@
+use crate::abstractions::entry::Entry;
+use chrono::Utc;
+use std::fmt;
+use url::Url;
+
+// Which identities of an entry are tried, in order, the
+// content hash is always the last resort
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FingerprintStrategy {
+    // guid, then canonical link
+    #[default]
+    GuidFirst,
+    // canonical link, then guid, for feeds regenerating
+    // their guids
+    LinkFirst,
+    // content hash only, changes when the entry is edited
+    Content,
+}
+
+// Stable identity of an entry, usable as a map key and
+// across runs
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Fingerprint {
+    Guid(String),
+    Link(String),
+    // FNV-1a hash of the normalized title, date and content
+    Content(u64),
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fingerprint::Guid(guid) => write!(f, "guid:{}", guid),
+            Fingerprint::Link(link) => write!(f, "link:{}", link),
+            Fingerprint::Content(hash) => write!(f, "hash:{:016x}", hash),
+        }
+    }
+}
+
+impl Entry {
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.fingerprint_with(FingerprintStrategy::default())
+    }
+
+    pub fn fingerprint_with(&self, strategy: FingerprintStrategy) -> Fingerprint {
+        let guid = || guid_identity(self).map(Fingerprint::Guid);
+        let link = || canonical_link(self).map(Fingerprint::Link);
+        let found = match strategy {
+            FingerprintStrategy::GuidFirst => guid().or_else(link),
+            FingerprintStrategy::LinkFirst => link().or_else(guid),
+            FingerprintStrategy::Content => None,
+        };
+        found.unwrap_or_else(|| Fingerprint::Content(content_hash(self)))
+    }
+}
+
+fn guid_identity(entry: &Entry) -> Option<String> {
+    let guid = entry.guid.value.trim();
+    if guid.is_empty() {
+        return None;
+    }
+    // permalinks are compared like links
+    if entry.guid.permalink {
+        if let Some(url) = normalize_url(guid) {
+            return Some(url);
+        }
+    }
+    Some(guid.to_string())
+}
+
+// The first alternate link, or else the first link
+fn canonical_link(entry: &Entry) -> Option<String> {
+    let links = entry.links.iter().filter(|l| !l.href.trim().is_empty());
+    let link = links
+        .clone()
+        .find(|l| l.rel == "alternate")
+        .or_else(|| links.clone().next())?;
+    Some(normalize_url(link.href.trim()).unwrap_or_else(|| link.href.trim().to_string()))
+}
+
+// Lowercase scheme and host, no default port, fragment or
+// tracking parameters
+fn normalize_url(value: &str) -> Option<String> {
+    let mut url = Url::parse(value).ok()?;
+    if url.cannot_be_a_base() {
+        return None;
+    }
+    url.set_fragment(None);
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_"))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+    Some(url.to_string())
+}
+
+fn content_hash(entry: &Entry) -> u64 {
+    let date = entry
+        .date()
+        .map(|d| d.with_timezone(&Utc).to_rfc3339())
+        .unwrap_or_default();
+    let body = entry
+        .content
+        .as_ref()
+        .and_then(|c| c.value.as_deref())
+        .or(entry.summary.as_ref().map(|s| s.value.as_str()))
+        .unwrap_or_default();
+    let mut hash = Fnv::default();
+    for part in [entry.title.value.as_str(), &date, body] {
+        hash.write(normalize_text(part).as_bytes());
+        // separator, so that parts cannot shift into each other
+        hash.write(&[0x1f]);
+    }
+    hash.0
+}
+
+// Lowercase text with collapsed whitespace and without markup
+pub(crate) fn normalize_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut in_tag = false;
+    for c in value.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if in_tag => {}
+            _ => text.extend(c.to_lowercase()),
+        }
+    }
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// 64-bit FNV-1a, unlike the standard hasher its output is
+// specified and stays the same between Rust versions
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstractions::{content::Content, guid::Guid, link::Link};
+    use std::collections::HashSet;
+
+    fn new_entry() -> Entry {
+        Entry {
+            title: "Title".to_string().into(),
+            guid: Guid {
+                value: String::new(),
+                permalink: false,
+            },
+            links: vec![Link::default()],
+            content: Some(Content {
+                value: Some("<p>Hello   world</p>".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn guid_first() {
+        let mut entry = new_entry();
+        entry.guid.value = "urn:1".into();
+        entry.links = vec!["https://example.org/1".to_string().into()];
+        assert_eq!(entry.fingerprint(), Fingerprint::Guid("urn:1".into()));
+        assert_eq!(
+            entry.fingerprint_with(FingerprintStrategy::LinkFirst),
+            Fingerprint::Link("https://example.org/1".into())
+        );
+    }
+    #[test]
+    fn link_normalized() {
+        let mut entry = new_entry();
+        entry.links = vec![
+            Link {
+                href: "https://example.org/enclosure.mp3".into(),
+                rel: "enclosure".into(),
+                ..Default::default()
+            },
+            "HTTPS://Example.org:443/1?utm_source=rss&id=2#top"
+                .to_string()
+                .into(),
+        ];
+        assert_eq!(
+            entry.fingerprint(),
+            Fingerprint::Link("https://example.org/1?id=2".into())
+        );
+    }
+    #[test]
+    fn content_hash_stable() {
+        let entry1 = new_entry();
+        let mut entry2 = new_entry();
+        entry2.title = "  TITLE ".to_string().into();
+        entry2.content.as_mut().unwrap().value = Some("hello world".into());
+        let fingerprint = entry1.fingerprint();
+        assert!(matches!(fingerprint, Fingerprint::Content(_)));
+        assert_eq!(fingerprint, entry2.fingerprint());
+        assert_eq!(fingerprint.to_string(), "hash:c6319cc1f3cff8de");
+        entry2.title = "Other".to_string().into();
+        assert_ne!(fingerprint, entry2.fingerprint());
+    }
+    #[test]
+    fn hash_set_keys() {
+        let mut entry2 = new_entry();
+        entry2.guid.value = "urn:2".into();
+        let keys: HashSet<Fingerprint> = [new_entry(), new_entry(), entry2]
+            .iter()
+            .map(|e| e.fingerprint())
+            .collect();
+        assert_eq!(keys.len(), 2);
+    }
+}
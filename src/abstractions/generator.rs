@@ -1,12 +1,28 @@
 use atom_syndication::Generator as AtomGenerator;
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Generator {
     pub value: String,
     pub uri: Option<String>,
     pub version: Option<String>,
 }
 
+impl From<String> for Generator {
+    fn from(value: String) -> Self {
+        Self {
+            value,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Generator> for String {
+    fn from(value: Generator) -> Self {
+        value.value
+    }
+}
+
 impl From<AtomGenerator> for Generator {
     fn from(value: AtomGenerator) -> Self {
         Self {
@@ -68,4 +84,18 @@ pub(crate) mod tests {
         let gen2 = new_generator();
         assert_eq!(gen1, gen2);
     }
+    #[test]
+    fn abstract_to_string_equal() {
+        let gen: String = new_generator().into();
+        assert_eq!(gen, String::from("Value"));
+    }
+    #[test]
+    fn string_to_abstact_equal() {
+        let gen1 = Generator {
+            value: String::from("Value"),
+            ..Default::default()
+        };
+        let gen2: Generator = String::from("Value").into();
+        assert_eq!(gen1, gen2);
+    }
 }
@@ -1,6 +1,7 @@
 use rss::Guid as RssGuid;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Guid {
     pub value: String,
     pub permalink: bool,
@@ -1,6 +1,7 @@
 use atom_syndication::Link as AtomLink;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Link {
     pub href: String,
     pub rel: String,
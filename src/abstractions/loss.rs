@@ -0,0 +1,114 @@
+use crate::abstractions::{
+    link::Link,
+    text::{Text, TextType},
+};
+use crate::error::Error;
+
+// Result of a conversion to a format that cannot hold every
+// abstract field
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checked<T> {
+    pub value: T,
+    // Path of every field that was dropped, e.g.
+    // "entries[0].updated"
+    pub lost: Vec<String>,
+}
+
+impl<T> Checked<T> {
+    pub fn is_lossless(&self) -> bool {
+        self.lost.is_empty()
+    }
+
+    // Refuse the conversion when anything was dropped
+    pub fn strict(self) -> Result<T, Error> {
+        if self.lost.is_empty() {
+            Ok(self.value)
+        } else {
+            Err(Error::LossyConversion(self.lost))
+        }
+    }
+}
+
+// Prefix the paths of the fields lost by a nested value
+pub(crate) fn nested(prefix: &str, lost: Vec<String>) -> impl Iterator<Item = String> + '_ {
+    lost.into_iter()
+        .map(move |field| format!("{}.{}", prefix, field))
+}
+
+// Attributes of a text RSS only keeps the value of
+pub(crate) fn text_losses(field: &str, text: &Text) -> Vec<String> {
+    let mut lost: Vec<String> = Vec::new();
+    if text.base.is_some() {
+        lost.push(format!("{}.base", field));
+    }
+    if text.lang.is_some() {
+        lost.push(format!("{}.lang", field));
+    }
+    if text.text_type != TextType::Text {
+        lost.push(format!("{}.text_type", field));
+    }
+    lost
+}
+
+// Attributes of a link RSS only keeps the href of
+pub(crate) fn link_losses(field: &str, link: &Link) -> Vec<String> {
+    let mut lost: Vec<String> = Vec::new();
+    if link.rel != "alternate" {
+        lost.push(format!("{}.rel", field));
+    }
+    if link.href_lang.is_some() {
+        lost.push(format!("{}.href_lang", field));
+    }
+    if link.mime_type.is_some() {
+        lost.push(format!("{}.mime_type", field));
+    }
+    if link.title.is_some() {
+        lost.push(format!("{}.title", field));
+    }
+    if link.length.is_some() {
+        lost.push(format!("{}.length", field));
+    }
+    lost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_refuses_losses() {
+        let checked = Checked {
+            value: 1,
+            lost: vec![],
+        };
+        assert!(checked.is_lossless());
+        assert_eq!(checked.strict().unwrap(), 1);
+        let checked = Checked {
+            value: 1,
+            lost: vec!["updated".into()],
+        };
+        assert!(matches!(
+            checked.strict(),
+            Err(Error::LossyConversion(lost)) if lost == vec!["updated".to_string()]
+        ));
+    }
+    #[test]
+    fn text_and_link_attributes() {
+        assert!(text_losses("title", &Text::from("T".to_string())).is_empty());
+        assert_eq!(
+            text_losses("title", &crate::abstractions::text::tests::new_text()),
+            vec!["title.base", "title.lang", "title.text_type"]
+        );
+        assert!(link_losses("links[0]", &Link::from("a".to_string())).is_empty());
+        assert_eq!(
+            link_losses("links[0]", &crate::abstractions::link::tests::new_link()),
+            vec![
+                "links[0].rel",
+                "links[0].href_lang",
+                "links[0].mime_type",
+                "links[0].title",
+                "links[0].length"
+            ]
+        );
+    }
+}
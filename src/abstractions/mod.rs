@@ -3,9 +3,12 @@ pub mod content;
 pub mod enclosure;
 pub mod entry;
 pub mod extension;
+pub mod feed;
+pub mod fingerprint;
 pub mod generator;
 pub mod guid;
 pub mod link;
+pub mod loss;
 pub mod person;
 pub mod source;
 pub mod text;
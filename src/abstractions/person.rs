@@ -1,27 +1,161 @@
+use crate::jsonfeed::Author as JsonPerson;
 use atom_syndication::Person as AtomPerson;
 
 #[derive(Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Person {
     pub name: String,
     pub email: Option<String>,
     pub uri: Option<String>,
 }
 
-impl From<String> for Person {
-    fn from(value: String) -> Self {
+// How a person is written in the RSS author and
+// managingEditor fields
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PersonFormat {
+    // jane@example.org (Jane Doe), the RSS 2.0 convention
+    #[default]
+    EmailName,
+    // Jane Doe <jane@example.org>
+    NameEmail,
+    Name,
+    Email,
+}
+
+impl Person {
+    // Read one person written as "email (Name)", "Name <email>",
+    // a bare email or a bare name
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if let Some(head) = value.strip_suffix('>') {
+            if let Some((name, email)) = head.rsplit_once('<') {
+                return Self {
+                    name: unquote(name.trim()).to_string(),
+                    email: Some(strip_mailto(email.trim()).to_string()),
+                    ..Default::default()
+                };
+            }
+        }
+        if let Some(head) = value.strip_suffix(')') {
+            if let Some((email, name)) = head.split_once('(') {
+                let email = strip_mailto(email.trim());
+                if is_email(email) {
+                    return Self {
+                        name: unquote(name.trim()).to_string(),
+                        email: Some(email.to_string()),
+                        ..Default::default()
+                    };
+                }
+            }
+        }
+        let email = strip_mailto(value);
+        if is_email(email) {
+            return Self {
+                email: Some(email.to_string()),
+                ..Default::default()
+            };
+        }
         Self {
-            name: value,
+            name: unquote(value).to_string(),
             ..Default::default()
         }
     }
+
+    // Read a list of persons separated by commas, semicolons
+    // or "and", separators inside parentheses, angle brackets
+    // or quotes are part of the person
+    pub fn parse_list(value: &str) -> Vec<Self> {
+        let mut persons: Vec<Self> = Vec::new();
+        let mut current = String::new();
+        let mut depth = 0usize;
+        let mut quoted = false;
+        let mut rest = value;
+        while let Some(c) = rest.chars().next() {
+            let at_top = depth == 0 && !quoted;
+            let and = rest
+                .get(..4)
+                .is_some_and(|w| w.eq_ignore_ascii_case("and "))
+                && current.ends_with(char::is_whitespace);
+            if at_top && (c == ',' || c == ';' || and) {
+                persons.extend(
+                    Some(current.trim())
+                        .filter(|s| !s.is_empty())
+                        .map(Self::parse),
+                );
+                current.clear();
+                rest = &rest[if and { 4 } else { 1 }..];
+                continue;
+            }
+            match c {
+                '"' => quoted = !quoted,
+                '(' | '<' if !quoted => depth += 1,
+                ')' | '>' if !quoted => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            current.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        persons.extend(
+            Some(current.trim())
+                .filter(|s| !s.is_empty())
+                .map(Self::parse),
+        );
+        persons
+    }
+
+    pub fn format(&self, format: PersonFormat) -> String {
+        match (format, &self.email) {
+            (_, None) => self.name.clone(),
+            (_, Some(email)) if self.name.is_empty() => email.clone(),
+            (PersonFormat::EmailName, Some(email)) => format!("{} ({})", email, self.name),
+            (PersonFormat::NameEmail, Some(email)) => format!("{} <{}>", self.name, email),
+            (PersonFormat::Name, Some(_)) => self.name.clone(),
+            (PersonFormat::Email, Some(email)) => email.clone(),
+        }
+    }
+}
+
+// Write persons in one RSS author field
+pub fn format_persons(persons: &[Person], format: PersonFormat) -> Option<String> {
+    if persons.is_empty() {
+        return None;
+    }
+    let persons: Vec<String> = persons.iter().map(|p| p.format(format)).collect();
+    Some(persons.join("; "))
+}
+
+fn strip_mailto(value: &str) -> &str {
+    value.strip_prefix("mailto:").unwrap_or(value)
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+fn is_email(value: &str) -> bool {
+    match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.is_empty()
+                && !domain.contains('@')
+                && !value.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+impl From<String> for Person {
+    fn from(value: String) -> Self {
+        Self::parse(&value)
+    }
 }
 
 impl From<Person> for String {
     fn from(value: Person) -> Self {
-        match value.email {
-            Some(email) => format!("{} ({})", email, value.name),
-            None => value.name,
-        }
+        value.format(PersonFormat::default())
     }
 }
 
@@ -37,14 +171,63 @@ impl From<AtomPerson> for Person {
 
 impl From<Person> for AtomPerson {
     fn from(value: Person) -> Self {
+        // Atom persons need a name, RSS ones may only
+        // have an email
+        let name = match (value.name.is_empty(), &value.email) {
+            (true, Some(email)) => email.clone(),
+            _ => value.name,
+        };
         Self {
-            name: value.name,
+            name,
             email: value.email,
             uri: value.uri,
+            extensions: Default::default(),
+        }
+    }
+}
+
+impl From<JsonPerson> for Person {
+    fn from(value: JsonPerson) -> Self {
+        Self {
+            name: value.name.unwrap_or_default(),
+            email: None,
+            uri: value.url,
         }
     }
 }
 
+impl From<Person> for JsonPerson {
+    fn from(value: Person) -> Self {
+        let name: Option<String> = if value.name.is_empty() {
+            None
+        } else {
+            Some(value.name)
+        };
+        Self {
+            name,
+            url: value.uri,
+            // no equivalent field on abstract person
+            avatar: None,
+        }
+    }
+}
+
+// Avatars of JSON Feed authors, the abstract person has no
+// field for them
+pub(crate) fn avatar_losses(authors: &[JsonPerson], author: Option<&JsonPerson>) -> Vec<String> {
+    let mut lost: Vec<String> = authors
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.avatar.is_some())
+        .map(|(i, _)| format!("authors[{}].avatar", i))
+        .collect();
+    // the JSON Feed 1.0 author is only read without authors
+    if authors.is_empty() && author.is_some_and(|a| a.avatar.is_some()) {
+        lost.push("author.avatar".into());
+    }
+    lost
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
@@ -60,6 +243,14 @@ pub(crate) mod tests {
             name: String::from("John"),
             email: Some(String::from("john@lenon.com")),
             uri: Some(String::from("https//lenon.com")),
+            extensions: Default::default(),
+        }
+    }
+    pub fn new_json_person() -> JsonPerson {
+        JsonPerson {
+            name: Some(String::from("John")),
+            url: Some(String::from("https//lenon.com")),
+            avatar: None,
         }
     }
     #[test]
@@ -102,10 +293,89 @@ pub(crate) mod tests {
     #[test]
     fn string_to_abstact_equal() {
         let per1 = Person {
-            name: String::from("john@lenon.com (John)"),
+            name: String::from("John"),
+            email: Some(String::from("john@lenon.com")),
             ..Default::default()
         };
         let per2: Person = String::from("john@lenon.com (John)").into();
         assert_eq!(per1, per2);
     }
+    #[test]
+    fn string_no_loss() {
+        let per1 = Person {
+            uri: None,
+            ..new_person()
+        };
+        for format in [PersonFormat::EmailName, PersonFormat::NameEmail] {
+            let per2 = Person::parse(&per1.format(format));
+            assert_eq!(per1, per2);
+        }
+    }
+    #[test]
+    fn parse_conventions() {
+        let per = Person::parse("John Lenon <mailto:john@lenon.com>");
+        assert_eq!(per.name, "John Lenon");
+        assert_eq!(per.email.as_deref(), Some("john@lenon.com"));
+        let per = Person::parse("john@lenon.com");
+        assert_eq!(per.name, "");
+        assert_eq!(per.email.as_deref(), Some("john@lenon.com"));
+        let per = Person::parse("John (Editor)");
+        assert_eq!(per.name, "John (Editor)");
+        assert_eq!(per.email, None);
+        let per = Person::parse("\"Lenon, John\" <john@lenon.com>");
+        assert_eq!(per.name, "Lenon, John");
+    }
+    #[test]
+    fn parse_list() {
+        let persons = Person::parse_list(
+            "john@lenon.com (John Lenon), Paul <paul@mccartney.com>; George and Ringo",
+        );
+        let names: Vec<&str> = persons.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["John Lenon", "Paul", "George", "Ringo"]);
+        assert_eq!(persons[1].email.as_deref(), Some("paul@mccartney.com"));
+        let persons = Person::parse_list("\"Lenon, John\" <john@lenon.com>, Andrew");
+        assert_eq!(persons.len(), 2);
+        assert_eq!(persons[1].name, "Andrew");
+    }
+    #[test]
+    fn format_persons_output() {
+        let persons = vec![
+            Person::parse("john@lenon.com (John)"),
+            Person::parse("Paul"),
+            Person::parse("george@harrison.com"),
+        ];
+        assert_eq!(
+            format_persons(&persons, PersonFormat::NameEmail).as_deref(),
+            Some("John <john@lenon.com>; Paul; george@harrison.com")
+        );
+        assert_eq!(
+            format_persons(&persons, PersonFormat::Name).as_deref(),
+            Some("John; Paul; george@harrison.com")
+        );
+        assert_eq!(format_persons(&[], PersonFormat::Email), None);
+    }
+    #[test]
+    fn email_only_to_atom() {
+        let per: AtomPerson = Person::parse("john@lenon.com").into();
+        assert_eq!(per.name, "john@lenon.com");
+    }
+    #[test]
+    fn default_abstract_to_json_equal() {
+        let per1: JsonPerson = Person::default().into();
+        let per2 = JsonPerson::default();
+        assert_eq!(per1, per2);
+    }
+    #[test]
+    fn default_json_to_abstract_equal() {
+        let per1: Person = JsonPerson::default().into();
+        let per2 = Person::default();
+        assert_eq!(per1, per2);
+    }
+    #[test]
+    fn json_no_loss() {
+        let per1: JsonPerson = new_json_person();
+        let per2: Person = per1.clone().into();
+        let per2: JsonPerson = per2.into();
+        assert_eq!(per1, per2)
+    }
 }
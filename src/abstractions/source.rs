@@ -1,10 +1,18 @@
-use super::{category::Category, generator::Generator, link::Link, person::Person, text::Text};
+use super::{
+    category::Category,
+    generator::Generator,
+    link::Link,
+    loss::{link_losses, text_losses},
+    person::Person,
+    text::Text,
+};
 use atom_syndication::{FixedDateTime, Source as AtomSource, Text as AtomText};
 use chrono::{DateTime, FixedOffset};
 use rss::Source as RssSource;
 use std::str::FromStr;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Source {
     pub title: Option<Text>,
     pub id: Option<String>,
@@ -73,6 +81,54 @@ impl Default for Source {
     }
 }
 
+impl Source {
+    // Fields From<Source> for RssSource has nowhere to put,
+    // RSS only keeps the url and the title
+    pub(crate) fn rss_losses(&self) -> Vec<String> {
+        let mut lost: Vec<String> = Vec::new();
+        if let Some(title) = &self.title {
+            lost.extend(text_losses("title", title));
+        }
+        if self.id.is_some() {
+            lost.push("id".into());
+        }
+        if self.updated.is_some() && self.updated != Source::default().updated {
+            lost.push("updated".into());
+        }
+        if !self.authors.is_empty() {
+            lost.push("authors".into());
+        }
+        if !self.categories.is_empty() {
+            lost.push("categories".into());
+        }
+        if !self.contributors.is_empty() {
+            lost.push("contributors".into());
+        }
+        if self.generator.is_some() {
+            lost.push("generator".into());
+        }
+        if self.icon.is_some() {
+            lost.push("icon".into());
+        }
+        if let Some(link) = self.links.first() {
+            lost.extend(link_losses("links[0]", link));
+        }
+        for i in 1..self.links.len() {
+            lost.push(format!("links[{}]", i));
+        }
+        if self.rights.is_some() {
+            lost.push("rights".into());
+        }
+        if self.subtitle.is_some() {
+            lost.push("subtitle".into());
+        }
+        if self.logo.is_some() {
+            lost.push("logo".into());
+        }
+        lost
+    }
+}
+
 impl From<AtomSource> for Source {
     fn from(value: AtomSource) -> Self {
         // Only need to store the value if it is
@@ -87,7 +143,7 @@ impl From<AtomSource> for Source {
         let id: Option<String> = if value.id.is_empty() {
             None
         } else {
-            Some(value.id.into())
+            Some(value.id)
         };
         Self {
             title,
@@ -118,11 +174,7 @@ impl From<Source> for AtomSource {
         } else {
             "".into()
         };
-        let updated: FixedDateTime = if let Some(s) = value.updated {
-            s
-        } else {
-            FixedDateTime::default()
-        };
+        let updated: FixedDateTime = value.updated.unwrap_or_default();
         Self {
             title,
             id,
@@ -215,6 +267,33 @@ pub(crate) mod tests {
         let src2 = new_rss_source();
         assert_eq!(src1, src2);
     }
+    #[test]
+    fn abstract_to_rss_losses() {
+        assert!(new_source_for_rss().rss_losses().is_empty());
+        assert!(Source::default().rss_losses().is_empty());
+        assert_eq!(
+            new_source().rss_losses(),
+            vec![
+                "title.base",
+                "title.lang",
+                "title.text_type",
+                "id",
+                "authors",
+                "categories",
+                "contributors",
+                "generator",
+                "icon",
+                "links[0].rel",
+                "links[0].href_lang",
+                "links[0].mime_type",
+                "links[0].title",
+                "links[0].length",
+                "rights",
+                "subtitle",
+                "logo"
+            ]
+        );
+    }
     // Rss source as only two fields. Check how to
     // test this properly
     #[test]
@@ -1,6 +1,7 @@
 use atom_syndication::{Text as AtomText, TextType as AtomTextType};
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TextType {
     #[default]
     Text,
@@ -29,6 +30,7 @@ impl From<TextType> for AtomTextType {
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text {
     pub value: String,
     pub base: Option<String>,
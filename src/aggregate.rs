@@ -0,0 +1,193 @@
+use crate::abstractions::{
+    entry::Entry,
+    feed::Feed,
+    fingerprint::{Fingerprint, FingerprintStrategy},
+    guid::Guid,
+    link::Link,
+    source::Source,
+    text::Text,
+};
+use std::{cmp::Reverse, collections::HashMap};
+
+// Metadata of the aggregated feed and how entries are merged
+#[derive(Debug, Clone, Default)]
+pub struct AggregateConfig {
+    pub title: Text,
+    pub id: Option<String>,
+    pub links: Vec<Link>,
+    // None keeps every entry
+    pub max_entries: Option<usize>,
+    pub fingerprint: FingerprintStrategy,
+}
+
+// Merge the entries of several feeds, newest first. Entries
+// with the same fingerprint are kept once, in their most
+// recently updated version, and every entry records its
+// origin feed as source. The result can be written in any
+// format with Feed::write_to.
+pub fn aggregate<F, I>(feeds: I, config: &AggregateConfig) -> Feed
+where
+    F: Into<Feed>,
+    I: IntoIterator<Item = F>,
+{
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut seen: HashMap<Fingerprint, usize> = HashMap::new();
+    for feed in feeds {
+        let mut feed: Feed = feed.into();
+        // entries leave the feed they inherit authors from
+        feed.inherit_authors();
+        let source = source_of(&feed);
+        for mut entry in std::mem::take(&mut feed.entries) {
+            if entry.source.is_none() {
+                entry.source = Some(source.clone());
+            }
+            let fingerprint = entry.fingerprint_with(config.fingerprint);
+            if entry.guid.value.trim().is_empty() {
+                entry.guid = guid_of(&fingerprint);
+            }
+            match seen.get(&fingerprint) {
+                Some(&i) => {
+                    if entry.updated > entries[i].updated {
+                        entries[i] = entry;
+                    }
+                }
+                None => {
+                    seen.insert(fingerprint, entries.len());
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+    // undated entries go last
+    entries.sort_by_key(|e| Reverse(e.date()));
+    if let Some(max) = config.max_entries {
+        entries.truncate(max);
+    }
+    let updated = entries
+        .iter()
+        .filter_map(Entry::updated_date)
+        .max()
+        .or(Feed::default().updated);
+    Feed {
+        title: config.title.clone(),
+        id: config.id.clone(),
+        links: config.links.clone(),
+        updated,
+        entries,
+        ..Default::default()
+    }
+}
+
+fn source_of(feed: &Feed) -> Source {
+    // RSS writes the first link as source url, which should
+    // be the feed itself
+    let mut links = feed.links.clone();
+    links.sort_by_key(|l| l.rel != "self");
+    Source {
+        title: Some(feed.title.clone()),
+        id: feed.id.clone(),
+        updated: feed.updated,
+        authors: feed.authors.clone(),
+        categories: feed.categories.clone(),
+        contributors: feed.contributors.clone(),
+        generator: feed.generator.clone(),
+        icon: feed.icon.clone(),
+        links,
+        rights: feed.rights.clone(),
+        subtitle: feed.subtitle.clone(),
+        logo: feed.logo.clone(),
+    }
+}
+
+// Atom requires an id, entries without guid get one from
+// their fingerprint
+fn guid_of(fingerprint: &Fingerprint) -> Guid {
+    match fingerprint {
+        Fingerprint::Guid(value) => Guid {
+            value: value.clone(),
+            permalink: false,
+        },
+        Fingerprint::Link(link) => Guid {
+            value: link.clone(),
+            permalink: true,
+        },
+        Fingerprint::Content(hash) => Guid {
+            value: format!("urn:fingerprint:{:016x}", hash),
+            permalink: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Feed as ModelFeed, FeedType};
+
+    const RSS_A: &str = r#"<rss version="2.0"><channel><title>A</title>
+<link>https://a.example.org</link><description>A</description>
+<item><title>A1</title><guid>https://a.example.org/1</guid>
+<pubDate>Mon, 01 Jan 2024 10:00:00 GMT</pubDate></item>
+<item><title>Shared</title><guid>urn:shared</guid>
+<pubDate>Wed, 03 Jan 2024 10:00:00 GMT</pubDate></item>
+<item><title>No guid</title><link>https://a.example.org/2</link></item>
+</channel></rss>"#;
+
+    const ATOM_B: &str = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>B</title>
+<id>urn:b</id><updated>2024-01-05T00:00:00Z</updated>
+<link rel="self" href="https://b.example.org/feed.xml"/>
+<author><name>Jane</name></author>
+<entry><title>Shared</title><id>urn:shared</id>
+<updated>2024-01-04T00:00:00Z</updated><published>2024-01-03T10:00:00Z</published></entry>
+<entry><title>B1</title><id>urn:b:1</id>
+<updated>2024-01-02T00:00:00Z</updated><published>2024-01-02T10:00:00Z</published></entry>
+</feed>"#;
+
+    fn feeds() -> Vec<ModelFeed> {
+        vec![
+            ModelFeed::read_from(RSS_A.as_bytes(), FeedType::Rss).unwrap(),
+            ModelFeed::read_from(ATOM_B.as_bytes(), FeedType::Atom).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn merge_sorted_deduplicated() {
+        let feed = aggregate(feeds(), &AggregateConfig::default());
+        let titles: Vec<&str> = feed
+            .entries
+            .iter()
+            .map(|e| e.title.value.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Shared", "B1", "A1", "No guid"]);
+        // the most recently updated copy wins
+        let shared = feed.entries[0].source.as_ref().unwrap();
+        assert_eq!(shared.id.as_deref(), Some("urn:b"));
+        assert_eq!(shared.links[0].href, "https://b.example.org/feed.xml");
+        assert_eq!(feed.entries[0].authors[0].name, "Jane");
+        assert_eq!(feed.entries[3].guid.value, "https://a.example.org/2");
+        assert_eq!(
+            feed.updated.unwrap().to_rfc3339(),
+            "2024-01-04T00:00:00+00:00"
+        );
+    }
+    #[test]
+    fn cap_and_write() {
+        let config = AggregateConfig {
+            title: "Planet".to_string().into(),
+            id: Some("urn:planet".into()),
+            max_entries: Some(2),
+            ..Default::default()
+        };
+        let feed = aggregate(feeds(), &config);
+        assert_eq!(feed.entries.len(), 2);
+        for ftype in [FeedType::Rss, FeedType::Atom] {
+            let buf = feed.write_to(Vec::new(), ftype).unwrap();
+            let read = ModelFeed::read_from(&buf[..], ftype).unwrap();
+            let read: Feed = read.into();
+            assert_eq!(read.entries.len(), 2);
+            assert_eq!(
+                read.entries[1].source.as_ref().unwrap().title,
+                Some("B".to_string().into())
+            );
+        }
+    }
+}
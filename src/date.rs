@@ -0,0 +1,442 @@
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+// Something the parser had to assume to build a date
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Guess {
+    // No time of day, midnight was used
+    MissingTime,
+    // No time zone, UTC was used
+    MissingZone,
+    // A zone name the parser does not know, UTC was used
+    UnknownZone(String),
+    // A two-digit year, expanded like RFC 2822 does: below
+    // 50 is in the 2000s
+    TwoDigitYear(u32),
+    // The weekday does not match the date and was ignored
+    WrongWeekday,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedDate {
+    pub date: DateTime<FixedOffset>,
+    // Empty when the value was a valid RFC 2822, RFC 3339 or
+    // W3C date
+    pub guesses: Vec<Guess>,
+}
+
+// Parse the dates found in real-world feeds: RFC 2822 and
+// its common mistakes (named or GMT+2 zones, missing
+// weekday, two-digit years), RFC 3339 and ISO 8601 variants
+pub fn parse(value: &str) -> Option<ParsedDate> {
+    let value = strip_comments(value.trim());
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(ParsedDate {
+            date,
+            guesses: vec![],
+        });
+    }
+    let bytes = value.as_bytes();
+    if bytes.len() >= 4 && bytes[..4].iter().all(u8::is_ascii_digit) {
+        parse_iso(value)
+    } else {
+        parse_textual(value)
+    }
+}
+
+// Same as parse, ignoring what was guessed
+pub fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    parse(value).map(|parsed| parsed.date)
+}
+
+// Zone names used in feeds, in seconds east of UTC; the
+// first ones are those of RFC 2822
+const ZONES: [(&str, i32); 32] = [
+    ("UT", 0),
+    ("UTC", 0),
+    ("GMT", 0),
+    ("Z", 0),
+    ("EST", -5 * 3600),
+    ("EDT", -4 * 3600),
+    ("CST", -6 * 3600),
+    ("CDT", -5 * 3600),
+    ("MST", -7 * 3600),
+    ("MDT", -6 * 3600),
+    ("PST", -8 * 3600),
+    ("PDT", -7 * 3600),
+    ("AKST", -9 * 3600),
+    ("AKDT", -8 * 3600),
+    ("HST", -10 * 3600),
+    ("WET", 0),
+    ("WEST", 3600),
+    ("BST", 3600),
+    ("CET", 3600),
+    ("CEST", 2 * 3600),
+    ("MET", 3600),
+    ("MEST", 2 * 3600),
+    ("EET", 2 * 3600),
+    ("EEST", 3 * 3600),
+    ("MSK", 3 * 3600),
+    ("IST", 5 * 3600 + 1800),
+    ("JST", 9 * 3600),
+    ("KST", 9 * 3600),
+    ("AWST", 8 * 3600),
+    ("ACST", 9 * 3600 + 1800),
+    ("AEST", 10 * 3600),
+    ("AEDT", 11 * 3600),
+];
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+// RFC 2822 comments, e.g. "+0000 (UTC)"
+fn strip_comments(value: &str) -> String {
+    let mut depth = 0;
+    value
+        .chars()
+        .filter(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' if depth > 0 => {
+                    depth -= 1;
+                    return false;
+                }
+                _ => {}
+            }
+            depth == 0
+        })
+        .collect()
+}
+
+// 2024-01-02T10:00:00+01:00 and its relatives: space as
+// separator, no seconds, no zone, basic format, partial
+// W3C dates
+fn parse_iso(value: &str) -> Option<ParsedDate> {
+    let mut guesses: Vec<Guess> = Vec::new();
+    let (date_part, rest) = match value.find(['T', 't', ' ']) {
+        Some(i) => (&value[..i], value[i + 1..].trim_start()),
+        None => (value, ""),
+    };
+    let date_part = date_part.replace('/', "-");
+    let date = match date_part.len() {
+        4 => NaiveDate::parse_from_str(&format!("{}-01-01", date_part), "%Y-%m-%d"),
+        7 => NaiveDate::parse_from_str(&format!("{}-01", date_part), "%Y-%m-%d"),
+        8 => NaiveDate::parse_from_str(&date_part, "%Y%m%d"),
+        _ => NaiveDate::parse_from_str(&date_part, "%Y-%m-%d"),
+    }
+    .ok()?;
+    // W3C dates without time are complete dates
+    if rest.is_empty() {
+        return build(date, NaiveTime::MIN, 0, guesses);
+    }
+    let time_end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '.' || c == ','))
+        .unwrap_or(rest.len());
+    let time = parse_time(&rest[..time_end])?;
+    let offset = zone(rest[time_end..].trim(), &mut guesses)?;
+    build(date, time, offset, guesses)
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    let value = value.replace(',', ".");
+    [
+        "%H:%M:%S%.f",
+        "%H:%M:%S",
+        "%H:%M",
+        "%H%M%S%.f",
+        "%H%M%S",
+        "%H%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveTime::parse_from_str(&value, format).ok())
+}
+
+// Offset of a zone, reporting a guess when it is missing
+// or unknown; None when it is malformed
+fn zone(value: &str, guesses: &mut Vec<Guess>) -> Option<i32> {
+    if value.is_empty() {
+        guesses.push(Guess::MissingZone);
+        return Some(0);
+    }
+    match parse_zone(value) {
+        Some(offset) => Some(offset),
+        None if value.chars().all(|c| c.is_ascii_alphabetic()) => {
+            guesses.push(Guess::UnknownZone(value.into()));
+            Some(0)
+        }
+        None => None,
+    }
+}
+
+// Z, +0200, +02:00, +2, GMT+2, UTC-05:00, EST...
+fn parse_zone(value: &str) -> Option<i32> {
+    let upper = value.to_ascii_uppercase();
+    if let Some((_, offset)) = ZONES.iter().find(|(name, _)| *name == upper) {
+        return Some(*offset);
+    }
+    let numeric = ["GMT", "UTC", "UT"]
+        .iter()
+        .find_map(|prefix| upper.strip_prefix(prefix))
+        .unwrap_or(&upper);
+    let (sign, digits) = match numeric.as_bytes().first()? {
+        b'+' => (1, &numeric[1..]),
+        b'-' => (-1, &numeric[1..]),
+        _ => return None,
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit() || b == b':') {
+        return None;
+    }
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() > 2 => digits.split_at(digits.len() - 2),
+        None => (digits, "0"),
+    };
+    if hours.is_empty() || hours.len() > 2 || minutes.len() > 2 {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+// Mon, 01 Jan 2024 10:00:00 GMT and its relatives: any
+// order of the tokens, full names, RFC 850 dashes, ctime
+fn parse_textual(value: &str) -> Option<ParsedDate> {
+    let mut guesses: Vec<Guess> = Vec::new();
+    let mut weekday: Option<usize> = None;
+    let mut day: Option<u32> = None;
+    let mut month: Option<u32> = None;
+    let mut year: Option<i32> = None;
+    let mut time: Option<NaiveTime> = None;
+    let mut offset: Option<i32> = None;
+    let mut pm: Option<bool> = None;
+    let tokens = value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        // 01-Jan-24, but not the UTC-05:00 zone
+        .flat_map(|t| {
+            if t.starts_with(|c: char| c.is_ascii_digit())
+                && t.contains(|c: char| c.is_ascii_alphabetic())
+            {
+                t.split('-').filter(|t| !t.is_empty()).collect::<Vec<_>>()
+            } else {
+                vec![t]
+            }
+        });
+    for token in tokens {
+        let lower = token.to_ascii_lowercase();
+        let lower = lower.trim_end_matches('.');
+        if token.contains(':') && time.is_none() && token.as_bytes()[0].is_ascii_digit() {
+            // a zone can be glued to the time
+            let end = token
+                .find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '.'))
+                .unwrap_or(token.len());
+            time = Some(parse_time(&token[..end])?);
+            if end < token.len() {
+                offset = Some(zone(&token[end..], &mut guesses)?);
+            }
+        } else if token.bytes().all(|b| b.is_ascii_digit()) {
+            let number: u32 = token.parse().ok()?;
+            if token.len() <= 2 && number <= 31 && day.is_none() {
+                day = Some(number);
+            } else if year.is_none() && token.len() == 4 {
+                year = Some(number as i32);
+            } else if year.is_none() && token.len() <= 2 {
+                let expanded = if number < 50 {
+                    2000 + number
+                } else {
+                    1900 + number
+                };
+                guesses.push(Guess::TwoDigitYear(number));
+                year = Some(expanded as i32);
+            } else {
+                return None;
+            }
+        } else if lower.len() >= 3 && MONTHS.iter().any(|m| lower.starts_with(m)) && month.is_none()
+        {
+            month = MONTHS
+                .iter()
+                .position(|m| lower.starts_with(m))
+                .map(|m| m as u32 + 1);
+        } else if lower.len() >= 3 && WEEKDAYS.iter().any(|d| lower.starts_with(d)) {
+            weekday = WEEKDAYS.iter().position(|d| lower.starts_with(d));
+        } else if lower == "am" || lower == "pm" {
+            pm = Some(lower == "pm");
+        } else if offset.is_none() {
+            offset = Some(zone(token, &mut guesses)?);
+        } else {
+            return None;
+        }
+    }
+    let date = NaiveDate::from_ymd_opt(year?, month?, day?)?;
+    if let Some(weekday) = weekday {
+        if date.weekday().num_days_from_monday() as usize != weekday {
+            guesses.push(Guess::WrongWeekday);
+        }
+    }
+    let mut time = match time {
+        Some(time) => time,
+        None => {
+            guesses.push(Guess::MissingTime);
+            NaiveTime::MIN
+        }
+    };
+    if let Some(pm) = pm {
+        let hour = time.hour() % 12 + if pm { 12 } else { 0 };
+        time = time.with_hour(hour)?;
+    }
+    let offset = match offset {
+        Some(offset) => offset,
+        None => {
+            if !guesses.contains(&Guess::MissingTime) {
+                guesses.push(Guess::MissingZone);
+            }
+            0
+        }
+    };
+    build(date, time, offset, guesses)
+}
+
+fn build(date: NaiveDate, time: NaiveTime, offset: i32, guesses: Vec<Guess>) -> Option<ParsedDate> {
+    let offset = FixedOffset::east_opt(offset)?;
+    let date = NaiveDateTime::new(date, time)
+        .and_local_timezone(offset)
+        .single()?;
+    Some(ParsedDate { date, guesses })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(value: &str, expected: &str, guesses: Vec<Guess>) {
+        let parsed = parse(value).unwrap_or_else(|| panic!("unable to parse {}", value));
+        assert_eq!(parsed.date.to_rfc3339(), expected, "{}", value);
+        assert_eq!(parsed.guesses, guesses, "{}", value);
+    }
+
+    #[test]
+    fn rfc2822() {
+        check(
+            "Mon, 01 Jan 2024 10:00:00 +0100",
+            "2024-01-01T10:00:00+01:00",
+            vec![],
+        );
+        check(
+            "Mon, 1 Jan 2024 10:00:00 GMT",
+            "2024-01-01T10:00:00+00:00",
+            vec![],
+        );
+        check(
+            "Mon, 01 Jan 2024 10:00:00 +0000 (UTC)",
+            "2024-01-01T10:00:00+00:00",
+            vec![],
+        );
+    }
+    #[test]
+    fn named_zones() {
+        check(
+            "Mon, 01 Jan 2024 10:00:00 EST",
+            "2024-01-01T10:00:00-05:00",
+            vec![],
+        );
+        check(
+            "Mon, 01 Jul 2024 10:00:00 pdt",
+            "2024-07-01T10:00:00-07:00",
+            vec![],
+        );
+        check(
+            "Mon, 01 Jan 2024 10:00:00 GMT+2",
+            "2024-01-01T10:00:00+02:00",
+            vec![],
+        );
+        check(
+            "Mon, 01 Jan 2024 10:00:00 UTC-05:30",
+            "2024-01-01T10:00:00-05:30",
+            vec![],
+        );
+        check(
+            "Mon, 01 Jan 2024 10:00:00 XYZT",
+            "2024-01-01T10:00:00+00:00",
+            vec![Guess::UnknownZone("XYZT".into())],
+        );
+    }
+    #[test]
+    fn sloppy_rfc2822() {
+        check(
+            "01 Jan 2024 10:00 +0100",
+            "2024-01-01T10:00:00+01:00",
+            vec![],
+        );
+        check(
+            "Monday, January 1, 2024 10:00:00 PM",
+            "2024-01-01T22:00:00+00:00",
+            vec![Guess::MissingZone],
+        );
+        check(
+            "Tue, 01 Jan 2024 10:00:00 GMT",
+            "2024-01-01T10:00:00+00:00",
+            vec![Guess::WrongWeekday],
+        );
+        check(
+            "Mon, 01 Jan 24 10:00:00 GMT",
+            "2024-01-01T10:00:00+00:00",
+            vec![Guess::TwoDigitYear(24)],
+        );
+        check(
+            "Thursday, 01-Jan-98 10:00:00 GMT",
+            "1998-01-01T10:00:00+00:00",
+            vec![Guess::TwoDigitYear(98)],
+        );
+        check(
+            "Mon Jan  1 10:00:00 UTC 2024",
+            "2024-01-01T10:00:00+00:00",
+            vec![],
+        );
+        check(
+            "1 Jan 2024",
+            "2024-01-01T00:00:00+00:00",
+            vec![Guess::MissingTime],
+        );
+    }
+    #[test]
+    fn iso8601() {
+        check("2024-01-01T10:00:00Z", "2024-01-01T10:00:00+00:00", vec![]);
+        check(
+            "2024-01-01T10:00:00.5+0100",
+            "2024-01-01T10:00:00.500+01:00",
+            vec![],
+        );
+        check(
+            "2024-01-01 10:00:00 +01:00",
+            "2024-01-01T10:00:00+01:00",
+            vec![],
+        );
+        check(
+            "2024-01-01T10:00",
+            "2024-01-01T10:00:00+00:00",
+            vec![Guess::MissingZone],
+        );
+        check("20240101T100000Z", "2024-01-01T10:00:00+00:00", vec![]);
+        check("2024-01-01", "2024-01-01T00:00:00+00:00", vec![]);
+        check("2024-03", "2024-03-01T00:00:00+00:00", vec![]);
+        check("2024", "2024-01-01T00:00:00+00:00", vec![]);
+    }
+    #[test]
+    fn invalid() {
+        assert!(parse("yesterday").is_none());
+        assert!(parse("").is_none());
+        assert!(parse("32 Jan 2024").is_none());
+        assert!(parse("2024-13-01").is_none());
+        assert!(parse("Mon, 01 Jan 2024 25:00:00 GMT").is_none());
+        // non-ASCII zones must not split inside a character
+        assert!(parse("1 Jan 2024 10:00 +é1").is_none());
+        assert!(parse("2024-01-01T10:00+é1").is_none());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{abstractions::feed::ATOM_NAMESPACE, models::FeedType};
+use quick_xml::{events::Event, name::ResolveResult, NsReader};
+
+pub const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+// Guess the format of a feed document by looking at its
+// first significant character and, for XML, at the name
+// and namespace of the root element
+pub fn detect(data: &[u8]) -> Option<FeedType> {
+    let data = data.strip_prefix(UTF8_BOM).unwrap_or(data);
+    let start = data.iter().position(|b| !b.is_ascii_whitespace())?;
+    match data[start] {
+        b'{' => Some(FeedType::Json),
+        b'<' => detect_xml(&data[start..]),
+        _ => None,
+    }
+}
+
+fn detect_xml(data: &[u8]) -> Option<FeedType> {
+    let mut reader = NsReader::from_reader(data);
+    loop {
+        match reader.read_resolved_event() {
+            Ok((ns, Event::Start(e))) | Ok((ns, Event::Empty(e))) => {
+                let namespace: Option<Vec<u8>> = match ns {
+                    ResolveResult::Bound(n) => Some(n.into_inner().to_vec()),
+                    _ => None,
+                };
+                let local = e.local_name();
+                return match (local.as_ref(), namespace.as_deref()) {
+                    // RSS 0.9x and 2.0 do not use a namespace
+                    (b"rss", _) => Some(FeedType::Rss),
+                    (b"feed", Some(n)) if n == ATOM_NAMESPACE.as_bytes() => Some(FeedType::Atom),
+                    (b"RDF", Some(n)) if n == RDF_NAMESPACE.as_bytes() => Some(FeedType::Rdf),
+                    _ => None,
+                };
+            }
+            // XML declaration, comments, doctype...
+            Ok((_, Event::Eof)) | Err(_) => return None,
+            Ok(_) => continue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_rss() {
+        let data = br#"<?xml version="1.0"?><rss version="2.0"><channel></channel></rss>"#;
+        assert_eq!(detect(data), Some(FeedType::Rss));
+    }
+    #[test]
+    fn detect_atom() {
+        let data = br#"<?xml version="1.0"?>
+            <!-- comment -->
+            <feed xmlns="http://www.w3.org/2005/Atom"></feed>"#;
+        assert_eq!(detect(data), Some(FeedType::Atom));
+    }
+    #[test]
+    fn detect_prefixed_atom() {
+        let data = br#"<a:feed xmlns:a="http://www.w3.org/2005/Atom"></a:feed>"#;
+        assert_eq!(detect(data), Some(FeedType::Atom));
+    }
+    #[test]
+    fn detect_feed_without_atom_namespace() {
+        let data = br#"<feed xmlns="http://purl.org/atom/ns#"></feed>"#;
+        assert_eq!(detect(data), None);
+    }
+    #[test]
+    fn detect_rdf() {
+        let data = br#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+            xmlns="http://purl.org/rss/1.0/"></rdf:RDF>"#;
+        assert_eq!(detect(data), Some(FeedType::Rdf));
+    }
+    #[test]
+    fn detect_json() {
+        let data = b"\xEF\xBB\xBF  {\"version\": \"https://jsonfeed.org/version/1.1\"}";
+        assert_eq!(detect(data), Some(FeedType::Json));
+    }
+    #[test]
+    fn detect_unknown() {
+        assert_eq!(detect(b"<html></html>"), None);
+        assert_eq!(detect(b"plain text"), None);
+        assert_eq!(detect(b""), None);
+    }
+}
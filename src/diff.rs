@@ -0,0 +1,161 @@
+use crate::abstractions::{
+    entry::Entry,
+    feed::Feed,
+    fingerprint::{Fingerprint, FingerprintStrategy},
+};
+use std::collections::{HashMap, HashSet};
+
+// Fields compared between two versions of an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EntryField {
+    Title,
+    Summary,
+    Content,
+    Updated,
+    Enclosure,
+    Categories,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modified<'a> {
+    pub old: &'a Entry,
+    pub new: &'a Entry,
+    pub fields: Vec<EntryField>,
+}
+
+// Changes between two snapshots, in the order of the
+// snapshot each entry comes from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntryDiff<'a> {
+    pub added: Vec<&'a Entry>,
+    pub removed: Vec<&'a Entry>,
+    pub modified: Vec<Modified<'a>>,
+}
+
+impl EntryDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+// Entries are matched by fingerprint, when a snapshot has
+// several entries with the same one only the first counts
+pub fn diff_entries<'a>(
+    old: &'a [Entry],
+    new: &'a [Entry],
+    strategy: FingerprintStrategy,
+) -> EntryDiff<'a> {
+    let mut old_entries: HashMap<Fingerprint, &'a Entry> = HashMap::new();
+    for entry in old {
+        old_entries
+            .entry(entry.fingerprint_with(strategy))
+            .or_insert(entry);
+    }
+    let mut diff = EntryDiff::default();
+    let mut matched: HashSet<Fingerprint> = HashSet::new();
+    for entry in new {
+        let fingerprint = entry.fingerprint_with(strategy);
+        if !matched.insert(fingerprint.clone()) {
+            continue;
+        }
+        match old_entries.get(&fingerprint) {
+            Some(previous) => {
+                let fields = changed_fields(previous, entry);
+                if !fields.is_empty() {
+                    diff.modified.push(Modified {
+                        old: previous,
+                        new: entry,
+                        fields,
+                    });
+                }
+            }
+            None => diff.added.push(entry),
+        }
+    }
+    let mut removed: HashSet<Fingerprint> = HashSet::new();
+    for entry in old {
+        let fingerprint = entry.fingerprint_with(strategy);
+        if !matched.contains(&fingerprint) && removed.insert(fingerprint) {
+            diff.removed.push(entry);
+        }
+    }
+    diff
+}
+
+pub fn diff_feeds<'a>(
+    old: &'a Feed,
+    new: &'a Feed,
+    strategy: FingerprintStrategy,
+) -> EntryDiff<'a> {
+    diff_entries(&old.entries, &new.entries, strategy)
+}
+
+fn changed_fields(old: &Entry, new: &Entry) -> Vec<EntryField> {
+    let mut fields: Vec<EntryField> = Vec::new();
+    if old.title != new.title {
+        fields.push(EntryField::Title);
+    }
+    if old.summary != new.summary {
+        fields.push(EntryField::Summary);
+    }
+    if old.content != new.content {
+        fields.push(EntryField::Content);
+    }
+    if old.updated != new.updated {
+        fields.push(EntryField::Updated);
+    }
+    if old.enclosure != new.enclosure {
+        fields.push(EntryField::Enclosure);
+    }
+    if old.categories != new.categories {
+        fields.push(EntryField::Categories);
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstractions::{category::Category, guid::Guid};
+
+    fn new_entry(id: &str, title: &str) -> Entry {
+        Entry {
+            guid: Guid {
+                value: id.into(),
+                permalink: false,
+            },
+            title: title.to_string().into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn added_removed_modified() {
+        let old = vec![
+            new_entry("urn:1", "One"),
+            new_entry("urn:2", "Two"),
+            new_entry("urn:3", "Three"),
+        ];
+        let mut two = new_entry("urn:2", "Two, edited");
+        two.categories = vec![Category {
+            name: "rust".into(),
+            ..Default::default()
+        }];
+        let new = vec![new_entry("urn:4", "Four"), two, new_entry("urn:3", "Three")];
+        let diff = diff_entries(&old, &new, FingerprintStrategy::default());
+        assert_eq!(diff.added, vec![&new[0]]);
+        assert_eq!(diff.removed, vec![&old[0]]);
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].old, &old[1]);
+        assert_eq!(
+            diff.modified[0].fields,
+            vec![EntryField::Title, EntryField::Categories]
+        );
+    }
+    #[test]
+    fn same_snapshot_is_empty() {
+        let entries = vec![new_entry("urn:1", "One"), new_entry("urn:1", "One again")];
+        let diff = diff_entries(&entries, &entries, FingerprintStrategy::default());
+        assert!(diff.is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::lenient::HTML_ENTITIES;
+use crate::models::FeedUrl;
+use std::collections::HashSet;
+use url::Url;
+
+// Paths where sites commonly serve a feed without linking it,
+// after the Wordpress, Jekyll and Hugo defaults
+const FALLBACK_PATHS: [&str; 4] = ["/feed", "/rss.xml", "/atom.xml", "/index.xml"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CandidateSource {
+    // a <link rel="alternate"> of the page
+    Link,
+    // a common path that may not exist
+    Fallback,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub url: FeedUrl,
+    pub title: Option<String>,
+    pub source: CandidateSource,
+}
+
+// Feeds of a HTML page, best first: the linked feeds in
+// document order with comment feeds last, then the fallback
+// paths of the site to probe
+pub fn discover(html: &[u8], page_url: &Url) -> Vec<Candidate> {
+    let html = String::from_utf8_lossy(html);
+    let mut base = page_url.clone();
+    let mut has_base = false;
+    let mut links: Vec<(bool, Candidate)> = Vec::new();
+    for (name, attrs) in tags(&html) {
+        let attr = |name: &str| {
+            attrs
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        match name.as_str() {
+            // only the first base element counts
+            "base" if !has_base => {
+                if let Some(url) = attr("href").and_then(|h| page_url.join(h).ok()) {
+                    base = url;
+                    has_base = true;
+                }
+            }
+            "link" => {
+                let alternate = attr("rel").is_some_and(|rel| {
+                    rel.split_ascii_whitespace()
+                        .any(|r| r.eq_ignore_ascii_case("alternate"))
+                });
+                let href = attr("href").map(str::trim).filter(|h| !h.is_empty());
+                let (Some(href), true) = (href, alternate) else {
+                    continue;
+                };
+                let Ok(url) = base.join(href) else {
+                    continue;
+                };
+                let Some(url) = attr("type").and_then(|t| feed_url(t, url.to_string())) else {
+                    continue;
+                };
+                let title = attr("title")
+                    .map(|t| t.split_whitespace().collect::<Vec<&str>>().join(" "))
+                    .filter(|t| !t.is_empty());
+                let comments = title
+                    .as_deref()
+                    .is_some_and(|t| t.to_lowercase().contains("comment"));
+                links.push((
+                    comments,
+                    Candidate {
+                        url,
+                        title,
+                        source: CandidateSource::Link,
+                    },
+                ));
+            }
+            _ => {}
+        }
+    }
+    // the sort is stable and keeps the document order
+    links.sort_by_key(|(comments, _)| *comments);
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut candidates: Vec<Candidate> = links
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .filter(|c| seen.insert(c.url.url().to_string()))
+        .collect();
+    for path in FALLBACK_PATHS {
+        let Ok(url) = page_url.join(path) else {
+            continue;
+        };
+        let url = url.to_string();
+        if !seen.insert(url.clone()) {
+            continue;
+        }
+        let url = match path {
+            "/atom.xml" => FeedUrl::Atom(url),
+            _ => FeedUrl::Rss(url),
+        };
+        candidates.push(Candidate {
+            url,
+            title: None,
+            source: CandidateSource::Fallback,
+        });
+    }
+    candidates
+}
+
+fn feed_url(mime_type: &str, url: String) -> Option<FeedUrl> {
+    let mime_type = mime_type.split(';').next()?.trim().to_ascii_lowercase();
+    // application/json is not accepted, pages link their API
+    // and oEmbed documents with it
+    match mime_type.as_str() {
+        "application/rss+xml" => Some(FeedUrl::Rss(url)),
+        "application/atom+xml" => Some(FeedUrl::Atom(url)),
+        "application/rdf+xml" => Some(FeedUrl::Rdf(url)),
+        "application/feed+json" => Some(FeedUrl::Json(url)),
+        _ => None,
+    }
+}
+
+// Start tags of a HTML document with their lowercase name and
+// their attributes, comments and the content of script and
+// style elements are skipped
+fn tags(html: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut tags = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let name_len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        if name_len == 0 || !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        let (attrs, len) = attributes(&rest[name_len..]);
+        rest = &rest[name_len + len..];
+        if name == "script" || name == "style" {
+            let close = format!("</{}", name);
+            rest = find_ignore_case(rest, &close).map_or("", |end| &rest[end..]);
+        }
+        tags.push((name, attrs));
+    }
+    tags
+}
+
+// Offset of an ASCII needle in the haystack, ignoring case
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+// Attributes of a start tag and the length up to its end
+fn attributes(tag: &str) -> (Vec<(String, String)>, usize) {
+    let mut attrs = Vec::new();
+    let bytes = tag.as_bytes();
+    let mut i = 0;
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+        if i >= bytes.len() {
+            return (attrs, i);
+        }
+        if bytes[i] == b'>' {
+            return (attrs, i + 1);
+        }
+        let start = i;
+        while i < bytes.len() && !b" \t\r\n\x0c=>/".contains(&bytes[i]) {
+            i += 1;
+        }
+        let name = tag[start..i].to_ascii_lowercase();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let mut value = String::new();
+        if i < bytes.len() && bytes[i] == b'=' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            let (raw, len) = match bytes.get(i) {
+                Some(quote @ (b'"' | b'\'')) => {
+                    let raw = &tag[i + 1..];
+                    match raw.find(*quote as char) {
+                        Some(end) => (&raw[..end], end + 2),
+                        None => (raw, raw.len() + 1),
+                    }
+                }
+                _ => {
+                    let raw = &tag[i..];
+                    let end = raw
+                        .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                        .unwrap_or(raw.len());
+                    (&raw[..end], end)
+                }
+            };
+            value = unescape(raw);
+            i += len;
+        }
+        attrs.push((name, value));
+    }
+}
+
+// Replace the character references of an attribute value
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|len| *len <= 32)
+            .and_then(|len| {
+                let name = &rest[1..len + 1];
+                let code = match name.strip_prefix('#') {
+                    Some(number) => match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => number.parse::<u32>().ok(),
+                    },
+                    None => match name {
+                        "amp" => Some(38),
+                        "lt" => Some(60),
+                        "gt" => Some(62),
+                        "quot" => Some(34),
+                        "apos" => Some(39),
+                        _ => HTML_ENTITIES
+                            .iter()
+                            .find(|(entity, _)| *entity == name)
+                            .map(|(_, code)| *code),
+                    },
+                };
+                Some((char::from_u32(code?)?, len + 2))
+            });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+<title>Blog</title>
+<!-- <link rel="alternate" type="application/rss+xml" href="/old.xml"> -->
+<link rel="stylesheet" href="/style.css">
+<link rel="alternate" type="application/rss+xml" title="Blog &raquo; Comments Feed" href="/comments/feed/">
+<LINK REL="Alternate" TYPE="application/atom+xml" TITLE="Blog" HREF=atom.xml>
+<link rel=alternate type='application/rss+xml; charset=utf-8' title='Blog &amp; more' href='/feed/?lang=en&amp;full=1'>
+<link rel="alternate" type="application/json" href="/wp-json/wp/v2/pages/1">
+<link rel="alternate" type="application/feed+json" href="https://cdn.example.net/feed.json">
+<script>document.write('<link rel="alternate" type="application/rss+xml" href="/js.xml">')</script>
+</head><body></body></html>"#;
+
+    #[test]
+    fn ranked_links() {
+        let page_url = Url::parse("https://example.org/blog/post.html").unwrap();
+        let candidates = discover(PAGE.as_bytes(), &page_url);
+        let urls: Vec<&FeedUrl> = candidates.iter().map(|c| &c.url).collect();
+        assert_eq!(
+            urls,
+            vec![
+                &FeedUrl::Atom("https://example.org/blog/atom.xml".into()),
+                &FeedUrl::Rss("https://example.org/feed/?lang=en&full=1".into()),
+                &FeedUrl::Json("https://cdn.example.net/feed.json".into()),
+                &FeedUrl::Rss("https://example.org/comments/feed/".into()),
+                &FeedUrl::Rss("https://example.org/feed".into()),
+                &FeedUrl::Rss("https://example.org/rss.xml".into()),
+                &FeedUrl::Atom("https://example.org/atom.xml".into()),
+                &FeedUrl::Rss("https://example.org/index.xml".into()),
+            ]
+        );
+        assert_eq!(candidates[0].title.as_deref(), Some("Blog"));
+        assert_eq!(candidates[1].title.as_deref(), Some("Blog & more"));
+        assert_eq!(
+            candidates[3].title.as_deref(),
+            Some("Blog \u{bb} Comments Feed")
+        );
+        assert_eq!(candidates[3].source, CandidateSource::Link);
+        assert_eq!(candidates[4].source, CandidateSource::Fallback);
+    }
+    #[test]
+    fn base_and_duplicates() {
+        let page = r#"<head><base href="https://static.example.org/">
+<link rel="alternate" type="application/atom+xml" href="/atom.xml">
+<link rel="alternate home" type="application/atom+xml" href="atom.xml"></head>"#;
+        let page_url = Url::parse("https://example.org/").unwrap();
+        let candidates = discover(page.as_bytes(), &page_url);
+        assert_eq!(
+            candidates[0].url,
+            FeedUrl::Atom("https://static.example.org/atom.xml".into())
+        );
+        assert_eq!(candidates.len(), 5);
+        // fallback paths are on the site of the page
+        assert_eq!(candidates[1].url.url(), "https://example.org/feed");
+    }
+    #[test]
+    fn script_content_skipped() {
+        let page = r#"<SCRIPT>x = '<link rel="alternate" type="application/atom+xml" href="/a.xml">'</Script>
+<link rel="alternate" type="application/atom+xml" href="/b.xml"><style>"#;
+        let page_url = Url::parse("https://example.org/").unwrap();
+        let candidates = discover(page.as_bytes(), &page_url);
+        assert_eq!(candidates[0].url.url(), "https://example.org/b.xml");
+        assert_eq!(candidates[1].source, CandidateSource::Fallback);
+        assert_eq!(find_ignore_case("a</STYLE>", "</style"), Some(1));
+    }
+    #[test]
+    fn unescape_references() {
+        assert_eq!(
+            unescape("a &amp; b &#233;&#xE9; &copy; &bogus; &"),
+            "a & b éé © &bogus; &"
+        );
+    }
+}
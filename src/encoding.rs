@@ -0,0 +1,222 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use std::borrow::Cow;
+
+// Where the encoding of a document was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingSource {
+    ByteOrderMark,
+    // Supplied by the caller, usually the HTTP charset
+    Hint,
+    // The encoding attribute of the XML declaration
+    Declaration,
+    // Nothing was declared: UTF-8, or Windows-1252 when the
+    // document is not valid UTF-8
+    Default,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectedEncoding {
+    pub encoding: &'static Encoding,
+    pub source: EncodingSource,
+    // Some bytes were replaced by U+FFFD while decoding
+    pub had_errors: bool,
+}
+
+impl DetectedEncoding {
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+}
+
+// Transcode a document to UTF-8 before parsing. The byte
+// order mark wins, then the hint and the XML declaration
+// in that order, unless the hint does not decode the
+// document cleanly and the declaration does. The XML
+// declaration of the result is rewritten to UTF-8.
+pub fn decode<'a>(data: &'a [u8], hint: Option<&str>) -> (Cow<'a, [u8]>, DetectedEncoding) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(data) {
+        return transcode(&data[bom_length..], encoding, EncodingSource::ByteOrderMark);
+    }
+    let hint = hint.and_then(charset).map(|e| (e, EncodingSource::Hint));
+    let declared = declared_encoding(data).map(|e| (e, EncodingSource::Declaration));
+    let candidates: Vec<(&'static Encoding, EncodingSource)> =
+        hint.into_iter().chain(declared).collect();
+    let mut fallback = None;
+    for (encoding, source) in candidates {
+        let (decoded, detected) = transcode(data, encoding, source);
+        if !detected.had_errors {
+            return (decoded, detected);
+        }
+        fallback.get_or_insert((decoded, detected));
+    }
+    if let Some(fallback) = fallback {
+        return fallback;
+    }
+    let encoding = match std::str::from_utf8(data) {
+        Ok(_) => UTF_8,
+        Err(_) => WINDOWS_1252,
+    };
+    transcode(data, encoding, EncodingSource::Default)
+}
+
+fn transcode<'a>(
+    data: &'a [u8],
+    encoding: &'static Encoding,
+    source: EncodingSource,
+) -> (Cow<'a, [u8]>, DetectedEncoding) {
+    let (text, had_errors) = encoding.decode_without_bom_handling(data);
+    let detected = DetectedEncoding {
+        encoding,
+        source,
+        had_errors,
+    };
+    let decoded = match text {
+        Cow::Borrowed(text)
+            if declared_name(text.as_bytes())
+                .map(|name| name.eq_ignore_ascii_case("utf-8"))
+                .unwrap_or(true) =>
+        {
+            Cow::Borrowed(text.as_bytes())
+        }
+        text => Cow::Owned(rewrite_declaration(&text).into_bytes()),
+    };
+    (decoded, detected)
+}
+
+// Encoding of a charset label or a Content-Type header
+// value, e.g. "text/xml; charset=Shift_JIS"
+fn charset(value: &str) -> Option<&'static Encoding> {
+    let label = match value.split_once(';') {
+        Some(_) => value.split(';').skip(1).find_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case("charset")
+                .then(|| value.trim().trim_matches('"'))
+        })?,
+        None => value.trim(),
+    };
+    Encoding::for_label(label.as_bytes())
+}
+
+fn declared_encoding(data: &[u8]) -> Option<&'static Encoding> {
+    // UTF-16 without byte order mark
+    match data {
+        [b'<', 0, b'?', 0, ..] => return Some(UTF_16LE),
+        [0, b'<', 0, b'?', ..] => return Some(UTF_16BE),
+        _ => {}
+    }
+    let encoding = Encoding::for_label(declared_name(data)?.as_bytes())?;
+    // the declaration was readable as bytes, it lies
+    (encoding != UTF_16LE && encoding != UTF_16BE).then_some(encoding)
+}
+
+// Value of the encoding attribute of the XML declaration
+fn declared_name(data: &[u8]) -> Option<String> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    if !data.starts_with(b"<?xml") {
+        return None;
+    }
+    let end = data.iter().position(|b| *b == b'>')?;
+    let declaration = String::from_utf8_lossy(&data[..end]);
+    let start = declaration.find("encoding")? + "encoding".len();
+    let rest = declaration[start..]
+        .trim_start()
+        .strip_prefix('=')?
+        .trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &rest[1..];
+    rest.find(quote).map(|end| rest[..end].to_string())
+}
+
+fn rewrite_declaration(text: &str) -> String {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let name = match declared_name(text.as_bytes()) {
+        Some(name) => name,
+        None => return text.to_string(),
+    };
+    let end = text.find('>').unwrap_or(0);
+    let declaration = text[..end].replacen(&name, "UTF-8", 1);
+    format!("{}{}", declaration, &text[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::SHIFT_JIS;
+
+    #[test]
+    fn utf8_unchanged() {
+        let data = "<?xml version=\"1.0\"?><rss><channel><title>Café</title></channel></rss>";
+        let (decoded, detected) = decode(data.as_bytes(), None);
+        assert!(matches!(decoded, Cow::Borrowed(_)));
+        assert_eq!(detected.encoding, UTF_8);
+        assert_eq!(detected.source, EncodingSource::Default);
+    }
+    #[test]
+    fn latin1_declaration() {
+        let data = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><title>Caf\xE9</title>";
+        let (decoded, detected) = decode(data, None);
+        assert_eq!(
+            String::from_utf8(decoded.into_owned()).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><title>Café</title>"
+        );
+        assert_eq!(detected.name(), "windows-1252");
+        assert_eq!(detected.source, EncodingSource::Declaration);
+    }
+    #[test]
+    fn undeclared_latin1() {
+        let (decoded, detected) = decode(b"<title>Caf\xE9</title>", None);
+        assert_eq!(&decoded[..], "<title>Café</title>".as_bytes());
+        assert_eq!(detected.encoding, WINDOWS_1252);
+        assert_eq!(detected.source, EncodingSource::Default);
+    }
+    #[test]
+    fn hint_wins_over_declaration() {
+        let (data, _, _) =
+            SHIFT_JIS.encode("<?xml version=\"1.0\" encoding=\"UTF-8\"?><title>日本</title>");
+        let (decoded, detected) = decode(&data, Some("text/xml; charset=Shift_JIS"));
+        assert_eq!(
+            String::from_utf8(decoded.into_owned()).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><title>日本</title>"
+        );
+        assert_eq!(detected.encoding, SHIFT_JIS);
+        assert_eq!(detected.source, EncodingSource::Hint);
+    }
+    #[test]
+    fn wrong_hint_falls_back_to_declaration() {
+        let data = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><title>Caf\xE9</title>";
+        let (decoded, detected) = decode(data, Some("text/xml; charset=utf-8"));
+        assert!(String::from_utf8(decoded.into_owned())
+            .unwrap()
+            .ends_with("Café</title>"));
+        assert_eq!(detected.encoding, WINDOWS_1252);
+        assert_eq!(detected.source, EncodingSource::Declaration);
+    }
+    #[test]
+    fn bom_wins() {
+        let mut data = vec![0xFF, 0xFE];
+        for unit in "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>é</a>".encode_utf16() {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        let (decoded, detected) = decode(&data, Some("windows-1252"));
+        assert_eq!(
+            String::from_utf8(decoded.into_owned()).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><a>é</a>"
+        );
+        assert_eq!(detected.encoding, UTF_16LE);
+        assert_eq!(detected.source, EncodingSource::ByteOrderMark);
+    }
+    #[test]
+    fn utf16_without_bom() {
+        let mut data = Vec::new();
+        for unit in "<?xml version=\"1.0\" encoding=\"UTF-16\"?><a>é</a>".encode_utf16() {
+            data.extend_from_slice(&unit.to_be_bytes());
+        }
+        let (decoded, detected) = decode(&data, None);
+        assert_eq!(
+            String::from_utf8(decoded.into_owned()).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><a>é</a>"
+        );
+        assert_eq!(detected.encoding, UTF_16BE);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::models::FeedType;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+// Line and column are 1-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    // Position of a byte offset in a document
+    pub fn from_offset(data: &[u8], offset: usize) -> Self {
+        let before = &data[..offset.min(data.len())];
+        let line = before.iter().filter(|b| **b == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .map(|p| p + 1)
+            .unwrap_or(0);
+        Self {
+            line,
+            column: String::from_utf8_lossy(&before[line_start..])
+                .chars()
+                .count()
+                + 1,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    // The document is not well-formed XML
+    // The source is boxed as atom_syndication hides the
+    // quick_xml error behind an opaque type
+    Xml {
+        source: Box<dyn StdError + Send + Sync>,
+        position: Option<Position>,
+    },
+    // The document is not valid JSON or not a JSON Feed
+    Json(serde_json::Error),
+    // The document is well-formed but is not the expected feed
+    InvalidFeed(String),
+    InvalidAttribute {
+        attribute: String,
+        value: String,
+    },
+    InvalidDate(String),
+    // Format detection failed
+    UnknownFormat,
+    // The operation needs another feed type, e.g. writing
+    // with FeedType::Auto from an abstract feed
+    UnsupportedFeedType(FeedType),
+    // A strict conversion would drop these fields
+    LossyConversion(Vec<String>),
+    // The offset is in bytes from the start of the query
+    InvalidQuery {
+        message: String,
+        offset: usize,
+    },
+    // WebSub content without a valid signature or for a
+    // topic that is not subscribed
+    RejectedPush(String),
+    // The server answered with an error status
+    Http {
+        url: String,
+        status: u16,
+        retry_after: Option<String>,
+    },
+    // The body is larger than the limit, in bytes
+    ResponseTooLarge(u64),
+    TooManyRedirects(String),
+    #[cfg(feature = "store")]
+    Sqlite(rusqlite::Error),
+    // The store was created by a newer version
+    UnsupportedSchema(u32),
+}
+
+impl Error {
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Error::Xml { position, .. } => *position,
+            Error::Json(err) if err.line() > 0 => Some(Position {
+                line: err.line(),
+                column: err.column(),
+            }),
+            _ => None,
+        }
+    }
+
+    // Fill the position of a XML error from the document
+    // that was being read
+    pub(crate) fn with_document(self, data: &[u8]) -> Self {
+        match self {
+            Error::Xml {
+                source,
+                position: None,
+            } => Error::Xml {
+                source,
+                position: crate::xml::syntax_error_position(data),
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Xml {
+                source,
+                position: Some(position),
+            } => write!(f, "XML error at {}: {}", position, source),
+            Error::Xml { source, .. } => write!(f, "XML error: {}", source),
+            Error::Json(err) => write!(f, "JSON error: {}", err),
+            Error::InvalidFeed(message) => write!(f, "invalid feed: {}", message),
+            Error::InvalidAttribute { attribute, value } => {
+                write!(f, "invalid value '{}' for attribute '{}'", value, attribute)
+            }
+            Error::InvalidDate(value) => write!(f, "invalid date: '{}'", value),
+            Error::UnknownFormat => write!(f, "unable to detect the feed type"),
+            Error::UnsupportedFeedType(ftype) => write!(f, "unsupported feed type: {:?}", ftype),
+            Error::LossyConversion(lost) => {
+                write!(f, "the conversion would lose: {}", lost.join(", "))
+            }
+            Error::InvalidQuery { message, offset } => {
+                write!(f, "invalid query at offset {}: {}", offset, message)
+            }
+            Error::RejectedPush(message) => write!(f, "rejected push: {}", message),
+            Error::Http { url, status, .. } => write!(f, "HTTP error {} for {}", status, url),
+            Error::ResponseTooLarge(limit) => {
+                write!(f, "the response is larger than {} bytes", limit)
+            }
+            Error::TooManyRedirects(url) => write!(f, "too many redirects, last to {}", url),
+            #[cfg(feature = "store")]
+            Error::Sqlite(err) => write!(f, "SQLite error: {}", err),
+            Error::UnsupportedSchema(version) => {
+                write!(f, "unsupported store schema version: {}", version)
+            }
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Xml { source, .. } => Some(source.as_ref()),
+            Error::Json(err) => Some(err),
+            #[cfg(feature = "store")]
+            Error::Sqlite(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(value: quick_xml::Error) -> Self {
+        Error::Xml {
+            source: Box::new(value),
+            position: None,
+        }
+    }
+}
+
+impl From<quick_xml::encoding::EncodingError> for Error {
+    fn from(value: quick_xml::encoding::EncodingError) -> Self {
+        quick_xml::Error::from(value).into()
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for Error {
+    fn from(value: quick_xml::events::attributes::AttrError) -> Self {
+        quick_xml::Error::from(value).into()
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Error::Json(value)
+    }
+}
+
+#[cfg(feature = "store")]
+impl From<rusqlite::Error> for Error {
+    fn from(value: rusqlite::Error) -> Self {
+        Error::Sqlite(value)
+    }
+}
+
+impl From<rss::Error> for Error {
+    fn from(value: rss::Error) -> Self {
+        match value {
+            rss::Error::Xml(err) => err.into(),
+            rss::Error::Utf8(err) => {
+                quick_xml::Error::from(quick_xml::encoding::EncodingError::from(err)).into()
+            }
+            rss::Error::InvalidStartTag => {
+                Error::InvalidFeed("the document does not start with a rss element".into())
+            }
+            rss::Error::Eof => Error::InvalidFeed("the document has no complete channel".into()),
+        }
+    }
+}
+
+impl From<atom_syndication::Error> for Error {
+    fn from(value: atom_syndication::Error) -> Self {
+        match value {
+            atom_syndication::Error::Xml(err) => Error::Xml {
+                source: Box::new(err),
+                position: None,
+            },
+            atom_syndication::Error::InvalidStartTag => {
+                Error::InvalidFeed("the document does not start with a feed element".into())
+            }
+            atom_syndication::Error::Eof => {
+                Error::InvalidFeed("the document has no complete feed".into())
+            }
+            atom_syndication::Error::WrongDatetime(date) => Error::InvalidDate(date),
+            atom_syndication::Error::WrongAttribute { attribute, value } => {
+                Error::InvalidAttribute {
+                    attribute: attribute.into(),
+                    value,
+                }
+            }
+            other => Error::InvalidFeed(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_from_offset() {
+        let data = b"<a>\n  <b>\n</a>";
+        assert_eq!(
+            Position::from_offset(data, 6),
+            Position { line: 2, column: 3 }
+        );
+        assert_eq!(
+            Position::from_offset(data, 0),
+            Position { line: 1, column: 1 }
+        );
+    }
+    #[test]
+    fn json_position() {
+        let err: Error = serde_json::from_str::<serde_json::Value>("{\n  \"a\": }")
+            .unwrap_err()
+            .into();
+        assert_eq!(err.position().map(|p| p.line), Some(2));
+        assert!(err.source().is_some());
+    }
+}
@@ -0,0 +1,404 @@
+use crate::error::Error;
+use crate::models::{Feed, FeedType, FeedUrl};
+use brotli::Decompressor as BrotliDecoder;
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::io::{self, Read};
+use std::time::Duration;
+use url::Url;
+
+const ACCEPT: &str = "application/atom+xml, application/rss+xml, application/feed+json, \
+    application/rdf+xml;q=0.9, application/xml;q=0.8, text/xml;q=0.8, */*;q=0.5";
+
+// Cache validators of the last response, to send back with
+// the next request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchConfig {
+    pub user_agent: String,
+    pub timeout: Duration,
+    pub max_redirects: u32,
+    // Limit of the body in bytes, before and after decoding
+    pub max_size: u64,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: concat!("feed_abstraction/", env!("CARGO_PKG_VERSION")).into(),
+            timeout: Duration::from_secs(30),
+            max_redirects: 5,
+            max_size: 10 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchResponse {
+    // None when the server answered 304 Not Modified
+    pub feed: Option<Feed>,
+    // Final URL after redirects, with the type of the feed
+    // read from it
+    pub url: FeedUrl,
+    // URL to use from now on, when every redirect followed was
+    // permanent
+    pub moved_to: Option<String>,
+    pub validators: Validators,
+    // Caching headers, left to the scheduler to interpret
+    pub cache_control: Option<String>,
+    pub expires: Option<String>,
+    pub retry_after: Option<String>,
+}
+
+// Download a feed. The validators of a previous response make
+// the request conditional, on 304 they are returned updated
+// and the feed is None.
+pub fn fetch(
+    url: &FeedUrl,
+    validators: &Validators,
+    config: &FetchConfig,
+) -> Result<FetchResponse, Error> {
+    // redirects are followed here to know whether they are
+    // all permanent
+    let agent = ureq::AgentBuilder::new()
+        .redirects(0)
+        .timeout(config.timeout)
+        .user_agent(&config.user_agent)
+        .build();
+    let mut current = url.url().to_string();
+    let mut permanent = true;
+    let mut redirects = 0;
+    let response = loop {
+        let mut request = agent
+            .get(&current)
+            .set("Accept", ACCEPT)
+            .set("Accept-Encoding", "gzip, deflate, br");
+        if let Some(etag) = &validators.etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                return Err(Error::Http {
+                    url: current,
+                    status,
+                    retry_after: response.header("Retry-After").map(String::from),
+                })
+            }
+            Err(ureq::Error::Transport(err)) => return Err(io::Error::other(err).into()),
+        };
+        let status = response.status();
+        let location = response.header("Location");
+        match (status, location) {
+            (301 | 302 | 303 | 307 | 308, Some(location)) => {
+                if redirects >= config.max_redirects {
+                    return Err(Error::TooManyRedirects(current));
+                }
+                let next = Url::parse(&current)
+                    .and_then(|base| base.join(location))
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                permanent &= matches!(status, 301 | 308);
+                redirects += 1;
+                current = next.to_string();
+            }
+            _ => break response,
+        }
+    };
+    let moved_to = (redirects > 0 && permanent).then(|| current.clone());
+    let header = |name: &str| response.header(name).map(String::from);
+    let new_validators = Validators {
+        etag: header("ETag"),
+        last_modified: header("Last-Modified"),
+    };
+    let cache_control = header("Cache-Control");
+    let expires = header("Expires");
+    let retry_after = header("Retry-After");
+    let content_type = header("Content-Type");
+    let content_encoding = header("Content-Encoding");
+    if response.status() == 304 {
+        return Ok(FetchResponse {
+            feed: None,
+            url: with_type(url.feed_type(), current),
+            moved_to,
+            // a 304 may only carry the validators that changed
+            validators: Validators {
+                etag: new_validators.etag.or(validators.etag.clone()),
+                last_modified: new_validators
+                    .last_modified
+                    .or(validators.last_modified.clone()),
+            },
+            cache_control,
+            expires,
+            retry_after,
+        });
+    }
+    let body = read_limited(response.into_reader(), config.max_size)?;
+    let body = decode(body, content_encoding.as_deref(), config.max_size)?;
+    let (feed, _) = Feed::read_with_encoding(&body[..], FeedType::Auto, content_type.as_deref())?;
+    Ok(FetchResponse {
+        url: with_type(feed.feed_type(), current),
+        feed: Some(feed),
+        moved_to,
+        validators: new_validators,
+        cache_control,
+        expires,
+        retry_after,
+    })
+}
+
+fn with_type(ftype: FeedType, url: String) -> FeedUrl {
+    match ftype {
+        FeedType::Atom => FeedUrl::Atom(url),
+        FeedType::Rdf => FeedUrl::Rdf(url),
+        FeedType::Json => FeedUrl::Json(url),
+        FeedType::Rss | FeedType::Auto => FeedUrl::Rss(url),
+    }
+}
+
+fn read_limited<R: Read>(reader: R, max_size: u64) -> Result<Vec<u8>, Error> {
+    let mut body: Vec<u8> = Vec::new();
+    reader.take(max_size + 1).read_to_end(&mut body)?;
+    if body.len() as u64 > max_size {
+        return Err(Error::ResponseTooLarge(max_size));
+    }
+    Ok(body)
+}
+
+// Undo the content codings, applied in the listed order
+fn decode(body: Vec<u8>, encoding: Option<&str>, max_size: u64) -> Result<Vec<u8>, Error> {
+    let Some(encoding) = encoding else {
+        return Ok(body);
+    };
+    let mut body = body;
+    for coding in encoding.rsplit(',').map(str::trim) {
+        body = match coding.to_ascii_lowercase().as_str() {
+            "" | "identity" => body,
+            "gzip" | "x-gzip" => read_limited(GzDecoder::new(&body[..]), max_size)?,
+            // deflate should be zlib wrapped, some servers send
+            // the raw stream
+            "deflate" if is_zlib(&body) => read_limited(ZlibDecoder::new(&body[..]), max_size)?,
+            "deflate" => read_limited(DeflateDecoder::new(&body[..]), max_size)?,
+            "br" => read_limited(BrotliDecoder::new(&body[..], 4096), max_size)?,
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsupported content encoding: {}", other),
+                )
+                .into())
+            }
+        };
+    }
+    Ok(body)
+}
+
+fn is_zlib(data: &[u8]) -> bool {
+    data.len() >= 2
+        && data[0] & 0x0f == 8
+        && (u16::from(data[0]) << 8 | u16::from(data[1])) % 31 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, write::ZlibEncoder, Compression};
+    use std::io::{Cursor, Write};
+    use tiny_http::{Header, Request, Response, Server};
+
+    const FEED: &str = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Blog</title>
+<id>urn:blog</id><updated>2024-01-02T00:00:00Z</updated>
+<entry><title>One</title><id>urn:1</id><updated>2024-01-02T00:00:00Z</updated></entry>
+</feed>"#;
+
+    // Local HTTP server answering with the handler, returns
+    // its base URL
+    fn serve<F>(handler: F) -> String
+    where
+        F: Fn(&Request) -> Response<Cursor<Vec<u8>>> + Send + 'static,
+    {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let response = handler(&request);
+                let _ = request.respond(response);
+            }
+        });
+        url
+    }
+
+    fn header(name: &str, value: &str) -> Header {
+        Header::from_bytes(name, value).unwrap()
+    }
+
+    fn request_header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+        request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str())
+    }
+
+    #[test]
+    fn conditional_get() {
+        let base = serve(|request| {
+            if request_header(request, "If-None-Match") == Some("\"v1\"") {
+                return Response::from_data(Vec::new())
+                    .with_status_code(304)
+                    .with_header(header("Cache-Control", "max-age=600"));
+            }
+            let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+            gzip.write_all(FEED.as_bytes()).unwrap();
+            Response::from_data(gzip.finish().unwrap())
+                .with_header(header("Content-Type", "application/atom+xml"))
+                .with_header(header("Content-Encoding", "gzip"))
+                .with_header(header("ETag", "\"v1\""))
+                .with_header(header("Last-Modified", "Tue, 02 Jan 2024 00:00:00 GMT"))
+        });
+        let url = FeedUrl::Rss(format!("{}/feed", base));
+        let config = FetchConfig::default();
+        let response = fetch(&url, &Validators::default(), &config).unwrap();
+        assert_eq!(response.url, FeedUrl::Atom(format!("{}/feed", base)));
+        assert_eq!(response.validators.etag.as_deref(), Some("\"v1\""));
+        let feed: crate::abstractions::feed::Feed = response.feed.unwrap().into();
+        assert_eq!(feed.entries.len(), 1);
+
+        let response = fetch(&url, &response.validators, &config).unwrap();
+        assert!(response.feed.is_none());
+        assert_eq!(response.validators.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            response.validators.last_modified.as_deref(),
+            Some("Tue, 02 Jan 2024 00:00:00 GMT")
+        );
+        assert_eq!(response.cache_control.as_deref(), Some("max-age=600"));
+    }
+    #[test]
+    fn content_encodings() {
+        let base = serve(|request| {
+            let (encoding, body) = match request.url() {
+                "/br" => {
+                    let mut body = Vec::new();
+                    let mut writer = brotli::CompressorWriter::new(&mut body, 4096, 5, 22);
+                    writer.write_all(FEED.as_bytes()).unwrap();
+                    drop(writer);
+                    ("br", body)
+                }
+                "/zlib" => {
+                    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+                    zlib.write_all(FEED.as_bytes()).unwrap();
+                    ("deflate", zlib.finish().unwrap())
+                }
+                _ => {
+                    let mut deflate =
+                        flate2::write::DeflateEncoder::new(Vec::new(), Compression::default());
+                    deflate.write_all(FEED.as_bytes()).unwrap();
+                    ("deflate", deflate.finish().unwrap())
+                }
+            };
+            Response::from_data(body).with_header(header("Content-Encoding", encoding))
+        });
+        for path in ["/br", "/zlib", "/raw"] {
+            let url = FeedUrl::Atom(format!("{}{}", base, path));
+            let response = fetch(&url, &Validators::default(), &FetchConfig::default()).unwrap();
+            assert!(response.feed.is_some(), "{}", path);
+        }
+    }
+    #[test]
+    fn redirects() {
+        let base = serve(|request| match request.url() {
+            "/old" => Response::from_data(Vec::new())
+                .with_status_code(301)
+                .with_header(header("Location", "/new")),
+            "/temporary" => Response::from_data(Vec::new())
+                .with_status_code(302)
+                .with_header(header("Location", "/old")),
+            "/loop" => Response::from_data(Vec::new())
+                .with_status_code(307)
+                .with_header(header("Location", "/loop")),
+            _ => Response::from_data(FEED.as_bytes().to_vec()),
+        });
+        let config = FetchConfig::default();
+        let response = fetch(
+            &FeedUrl::Atom(format!("{}/old", base)),
+            &Validators::default(),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(response.moved_to, Some(format!("{}/new", base)));
+        let response = fetch(
+            &FeedUrl::Atom(format!("{}/temporary", base)),
+            &Validators::default(),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(response.url.url(), format!("{}/new", base));
+        assert_eq!(response.moved_to, None);
+        assert!(matches!(
+            fetch(
+                &FeedUrl::Atom(format!("{}/loop", base)),
+                &Validators::default(),
+                &config
+            ),
+            Err(Error::TooManyRedirects(_))
+        ));
+    }
+    #[test]
+    fn limits_and_errors() {
+        let base = serve(|request| match request.url() {
+            "/busy" => Response::from_data(Vec::new())
+                .with_status_code(503)
+                .with_header(header("Retry-After", "120")),
+            "/bomb" => {
+                let mut gzip = GzEncoder::new(Vec::new(), Compression::best());
+                gzip.write_all(&vec![b' '; 1 << 20]).unwrap();
+                Response::from_data(gzip.finish().unwrap())
+                    .with_header(header("Content-Encoding", "gzip"))
+            }
+            _ => Response::from_data(FEED.as_bytes().to_vec()),
+        });
+        let config = FetchConfig {
+            max_size: 64 * 1024,
+            ..Default::default()
+        };
+        let fetch_path = |path: &str| {
+            fetch(
+                &FeedUrl::Atom(format!("{}{}", base, path)),
+                &Validators::default(),
+                &config,
+            )
+        };
+        match fetch_path("/busy") {
+            Err(Error::Http {
+                status,
+                retry_after,
+                ..
+            }) => {
+                assert_eq!(status, 503);
+                assert_eq!(retry_after.as_deref(), Some("120"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            fetch_path("/bomb"),
+            Err(Error::ResponseTooLarge(65536))
+        ));
+        let config = FetchConfig {
+            max_size: 16,
+            ..Default::default()
+        };
+        assert!(matches!(
+            fetch(
+                &FeedUrl::Atom(format!("{}/feed", base)),
+                &Validators::default(),
+                &config
+            ),
+            Err(Error::ResponseTooLarge(16))
+        ));
+    }
+}
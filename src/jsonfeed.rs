@@ -0,0 +1,223 @@
+use crate::error::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{ser::PrettyFormatter, Serializer, Value};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+
+// See https://www.jsonfeed.org/version/1.1/
+pub const VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Feed {
+    pub version: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub home_page_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<Author>,
+    // JSON Feed 1.0, superseded by authors
+    #[serde(default, skip_serializing)]
+    pub author: Option<Author>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expired: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hubs: Vec<Hub>,
+    #[serde(default)]
+    pub items: Vec<Item>,
+    // Every unknown key, extensions start with an underscore
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        Self {
+            version: VERSION.into(),
+            title: Default::default(),
+            home_page_url: Default::default(),
+            feed_url: Default::default(),
+            description: Default::default(),
+            user_comment: Default::default(),
+            next_url: Default::default(),
+            icon: Default::default(),
+            favicon: Default::default(),
+            authors: Default::default(),
+            author: Default::default(),
+            language: Default::default(),
+            expired: Default::default(),
+            hubs: Default::default(),
+            items: Default::default(),
+            extensions: Default::default(),
+        }
+    }
+}
+
+impl Feed {
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Feed, Error> {
+        let feed: Feed = serde_json::from_reader(reader)?;
+        Ok(feed)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<W, Error> {
+        let mut writer = writer;
+        serde_json::to_writer(&mut writer, self)?;
+        Ok(writer)
+    }
+
+    pub fn pretty_write_to<W: Write>(
+        &self,
+        writer: W,
+        indent_char: u8,
+        indent_size: usize,
+    ) -> Result<W, Error> {
+        let indent: Vec<u8> = vec![indent_char; indent_size];
+        let formatter = PrettyFormatter::with_indent(&indent);
+        let mut serializer = Serializer::with_formatter(writer, formatter);
+        self.serialize(&mut serializer)?;
+        Ok(serializer.into_inner())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    // Some publishers use numbers
+    #[serde(deserialize_with = "string_or_number")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_html: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banner_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_published: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<Author>,
+    // JSON Feed 1.0, superseded by authors
+    #[serde(default, skip_serializing)]
+    pub author: Option<Author>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    // Every unknown key, extensions start with an underscore
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Author {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub url: String,
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_in_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_in_seconds: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Hub {
+    #[serde(rename = "type")]
+    pub hub_type: String,
+    pub url: String,
+}
+
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "expected a string or a number, got {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = r#"{
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": "My Example Feed",
+        "home_page_url": "https://example.org/",
+        "feed_url": "https://example.org/feed.json",
+        "author": { "name": "Legacy" },
+        "_blue_shed": { "about": "https://blueshed-podcasts.com/json-feed-extension-docs" },
+        "items": [
+            {
+                "id": 2,
+                "content_text": "This is a second item.",
+                "url": "https://example.org/second-item",
+                "tags": ["rust"]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn read_json_feed() {
+        let feed = Feed::read_from(FEED.as_bytes()).unwrap();
+        assert_eq!(feed.title, "My Example Feed");
+        assert_eq!(feed.author.unwrap().name, Some("Legacy".into()));
+        assert!(feed.extensions.contains_key("_blue_shed"));
+        assert_eq!(feed.items.len(), 1);
+        assert_eq!(feed.items[0].id, "2");
+        assert_eq!(feed.items[0].tags, vec!["rust".to_string()]);
+    }
+    #[test]
+    fn json_no_loss() {
+        let mut feed1 = Feed::read_from(FEED.as_bytes()).unwrap();
+        // legacy author is never written back
+        feed1.author = None;
+        let buf = feed1.write_to(Vec::new()).unwrap();
+        let feed2 = Feed::read_from(&buf[..]).unwrap();
+        assert_eq!(feed1, feed2);
+    }
+    #[test]
+    fn pretty_write() {
+        let feed = Feed::default();
+        let buf = feed.pretty_write_to(Vec::new(), b'\t', 1).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.starts_with("{\n\t\"version\""));
+    }
+}
@@ -0,0 +1,1036 @@
+use crate::date::parse_date;
+use crate::detect::detect;
+use crate::error::{Error, Position};
+use crate::jsonfeed::{Feed as JsonFeed, Item as JsonItem};
+use crate::models::{Feed, FeedType};
+use crate::rdf;
+use atom_syndication::Feed as AtomFeed;
+use rss::Channel as RssFeed;
+use std::fmt;
+
+// Something that was repaired or dropped while reading
+// a malformed feed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    // A bare & was escaped
+    UnescapedAmpersand(Position),
+    // A bare < in text was escaped
+    UnescapedLessThan(Position),
+    // An entity that XML does not define; HTML entities are
+    // replaced by their character, others are escaped
+    UndeclaredEntity {
+        name: String,
+        position: Position,
+        replaced: bool,
+    },
+    // Markup inside a text element was stripped or escaped
+    StrayMarkup {
+        element: String,
+        position: Position,
+    },
+    // Quotes were added around an attribute value
+    UnquotedAttribute {
+        name: String,
+        position: Position,
+    },
+    // A closing tag without start tag was dropped
+    UnmatchedClosingTag {
+        name: String,
+        position: Position,
+    },
+    // A closing tag was added
+    UnclosedElement {
+        name: String,
+        position: Position,
+    },
+    // An entry that could not be salvaged was dropped
+    SkippedEntry {
+        position: Option<Position>,
+        reason: String,
+    },
+    // A date the parser rejected was rewritten, or dropped
+    // when replacement is None
+    InvalidDate {
+        value: String,
+        replacement: Option<String>,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::UnescapedAmpersand(position) => {
+                write!(f, "{}: escaped a bare '&'", position)
+            }
+            Warning::UnescapedLessThan(position) => {
+                write!(f, "{}: escaped a bare '<'", position)
+            }
+            Warning::UndeclaredEntity {
+                name,
+                position,
+                replaced: true,
+            } => write!(f, "{}: replaced undeclared entity &{};", position, name),
+            Warning::UndeclaredEntity { name, position, .. } => {
+                write!(f, "{}: escaped undeclared entity &{};", position, name)
+            }
+            Warning::StrayMarkup { element, position } => {
+                write!(f, "{}: removed markup inside <{}>", position, element)
+            }
+            Warning::UnquotedAttribute { name, position } => {
+                write!(f, "{}: quoted the value of {}", position, name)
+            }
+            Warning::UnmatchedClosingTag { name, position } => {
+                write!(f, "{}: dropped unmatched </{}>", position, name)
+            }
+            Warning::UnclosedElement { name, position } => {
+                write!(f, "{}: closed <{}>", position, name)
+            }
+            Warning::SkippedEntry {
+                position: Some(position),
+                reason,
+            } => write!(f, "{}: skipped entry, {}", position, reason),
+            Warning::SkippedEntry { reason, .. } => write!(f, "skipped entry, {}", reason),
+            Warning::InvalidDate {
+                value,
+                replacement: Some(replacement),
+            } => write!(f, "rewrote date '{}' as '{}'", value, replacement),
+            Warning::InvalidDate { value, .. } => write!(f, "dropped date '{}'", value),
+        }
+    }
+}
+
+// Read a feed, repairing the most common breakage instead
+// of failing
+pub(crate) fn read_from_slice(data: &[u8], ftype: FeedType) -> Result<(Feed, Vec<Warning>), Error> {
+    let ftype = match ftype {
+        FeedType::Auto => match detect(data) {
+            Some(ftype) => ftype,
+            None => {
+                let (repaired, _) = repair(data, FeedType::Auto);
+                detect(&repaired).ok_or(Error::UnknownFormat)?
+            }
+        },
+        ftype => ftype,
+    };
+    if ftype == FeedType::Json {
+        return read_json(data);
+    }
+    let (mut repaired, mut warnings) = repair(data, ftype);
+    let feed = match ftype {
+        FeedType::Rss => Feed::Rss(RssFeed::read_from(&repaired[..])?),
+        FeedType::Rdf => Feed::Rdf(rdf::read_from(&repaired[..])?),
+        _ => loop {
+            // atom_syndication rejects the whole feed on the
+            // first date it cannot parse
+            match AtomFeed::read_from(&repaired[..]) {
+                Ok(feed) => break Feed::Atom(feed),
+                Err(atom_syndication::Error::WrongDatetime(value)) => {
+                    let (fixed, warning) =
+                        repair_date(&repaired, &value).ok_or(Error::InvalidDate(value))?;
+                    repaired = fixed;
+                    warnings.push(warning);
+                }
+                Err(err) => return Err(Error::from(err).with_document(&repaired)),
+            }
+        },
+    };
+    Ok((feed, warnings))
+}
+
+// Items that do not deserialize are dropped, the rest of
+// the document must be valid JSON
+fn read_json(data: &[u8]) -> Result<(Feed, Vec<Warning>), Error> {
+    let mut warnings: Vec<Warning> = Vec::new();
+    let mut value: serde_json::Value = serde_json::from_slice(data)?;
+    if let Some(items) = value.get_mut("items").and_then(|v| v.as_array_mut()) {
+        let mut index = 0;
+        items.retain(|item| {
+            let result = serde_json::from_value::<JsonItem>(item.clone());
+            if let Err(err) = &result {
+                warnings.push(Warning::SkippedEntry {
+                    position: None,
+                    reason: format!("item {}: {}", index, err),
+                });
+            }
+            index += 1;
+            result.is_ok()
+        });
+    }
+    let feed: JsonFeed = serde_json::from_value(value)?;
+    Ok((Feed::Json(feed), warnings))
+}
+
+// Rewrite a date element the Atom parser rejected, or
+// remove it when it cannot be understood
+fn repair_date(data: &[u8], value: &str) -> Option<(Vec<u8>, Warning)> {
+    let (element_start, start, end, element_end) = find_date_element(data, value)?;
+    let mut fixed: Vec<u8> = Vec::with_capacity(data.len());
+    match parse_date(value) {
+        Some(date) => {
+            let replacement = date.to_rfc3339();
+            fixed.extend_from_slice(&data[..start]);
+            fixed.extend_from_slice(replacement.as_bytes());
+            fixed.extend_from_slice(&data[end..]);
+            Some((
+                fixed,
+                Warning::InvalidDate {
+                    value: value.into(),
+                    replacement: Some(replacement),
+                },
+            ))
+        }
+        None => {
+            fixed.extend_from_slice(&data[..element_start]);
+            fixed.extend_from_slice(&data[element_end..]);
+            Some((
+                fixed,
+                Warning::InvalidDate {
+                    value: value.into(),
+                    replacement: None,
+                },
+            ))
+        }
+    }
+}
+
+// The first updated or published element holding the value,
+// as the offsets of the element, of its text and of their
+// ends; an element of another name with the same text is
+// left alone
+fn find_date_element(data: &[u8], value: &str) -> Option<(usize, usize, usize, usize)> {
+    let value = value.trim().as_bytes();
+    if value.is_empty() {
+        return None;
+    }
+    let mut offset = 0;
+    while let Some(found) = find(&data[offset..], value) {
+        let start = offset + found;
+        let end = start + value.len();
+        offset = start + 1;
+        // the text of the element, whitespace aside
+        let text_start = data[..start]
+            .iter()
+            .rposition(|b| !b.is_ascii_whitespace())
+            .filter(|i| data[*i] == b'>')
+            .map(|i| i + 1);
+        let text_end = data[end..]
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .map(|i| end + i)
+            .filter(|i| data[*i] == b'<');
+        let (Some(text_start), Some(text_end)) = (text_start, text_end) else {
+            continue;
+        };
+        let Some(element_start) = data[..text_start].iter().rposition(|b| *b == b'<') else {
+            continue;
+        };
+        let tag = &data[element_start + 1..text_start - 1];
+        let name_len = tag
+            .iter()
+            .position(|b| b.is_ascii_whitespace() || *b == b'/')
+            .unwrap_or(tag.len());
+        let name = &tag[..name_len];
+        let local = name.rsplit(|b| *b == b':').next().unwrap_or(name);
+        if local != b"updated" && local != b"published" {
+            continue;
+        }
+        let mut close = b"</".to_vec();
+        close.extend_from_slice(name);
+        let Some(rest) = data[text_end..].strip_prefix(close.as_slice()) else {
+            continue;
+        };
+        let Some(close_len) = rest.iter().position(|b| *b == b'>') else {
+            continue;
+        };
+        if !rest[..close_len].iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let element_end = text_end + close.len() + close_len + 1;
+        return Some((element_start, start, end, element_end));
+    }
+    None
+}
+
+// What to do with markup found inside an element that
+// should only hold text
+#[derive(Clone, Copy, PartialEq)]
+enum TextAction {
+    Strip,
+    Escape,
+}
+
+const PREDEFINED_ENTITIES: [&str; 5] = ["amp", "lt", "gt", "quot", "apos"];
+
+// The HTML entities commonly found in feeds
+pub(crate) const HTML_ENTITIES: [(&str, u32); 96] = [
+    ("nbsp", 160),
+    ("iexcl", 161),
+    ("cent", 162),
+    ("pound", 163),
+    ("yen", 165),
+    ("sect", 167),
+    ("uml", 168),
+    ("copy", 169),
+    ("ordf", 170),
+    ("laquo", 171),
+    ("shy", 173),
+    ("reg", 174),
+    ("deg", 176),
+    ("sup2", 178),
+    ("sup3", 179),
+    ("acute", 180),
+    ("para", 182),
+    ("middot", 183),
+    ("ordm", 186),
+    ("raquo", 187),
+    ("frac14", 188),
+    ("frac12", 189),
+    ("frac34", 190),
+    ("iquest", 191),
+    ("Agrave", 192),
+    ("Aacute", 193),
+    ("Acirc", 194),
+    ("Auml", 196),
+    ("Aring", 197),
+    ("AElig", 198),
+    ("Ccedil", 199),
+    ("Egrave", 200),
+    ("Eacute", 201),
+    ("Ntilde", 209),
+    ("Ouml", 214),
+    ("times", 215),
+    ("Oslash", 216),
+    ("Uuml", 220),
+    ("szlig", 223),
+    ("agrave", 224),
+    ("aacute", 225),
+    ("acirc", 226),
+    ("atilde", 227),
+    ("auml", 228),
+    ("aring", 229),
+    ("aelig", 230),
+    ("ccedil", 231),
+    ("egrave", 232),
+    ("eacute", 233),
+    ("ecirc", 234),
+    ("euml", 235),
+    ("igrave", 236),
+    ("iacute", 237),
+    ("icirc", 238),
+    ("iuml", 239),
+    ("ntilde", 241),
+    ("ograve", 242),
+    ("oacute", 243),
+    ("ocirc", 244),
+    ("otilde", 245),
+    ("ouml", 246),
+    ("divide", 247),
+    ("oslash", 248),
+    ("ugrave", 249),
+    ("uacute", 250),
+    ("ucirc", 251),
+    ("uuml", 252),
+    ("yuml", 255),
+    ("ensp", 8194),
+    ("emsp", 8195),
+    ("thinsp", 8201),
+    ("zwnj", 8204),
+    ("zwj", 8205),
+    ("lrm", 8206),
+    ("rlm", 8207),
+    ("ndash", 8211),
+    ("mdash", 8212),
+    ("lsquo", 8216),
+    ("rsquo", 8217),
+    ("sbquo", 8218),
+    ("ldquo", 8220),
+    ("rdquo", 8221),
+    ("bdquo", 8222),
+    ("dagger", 8224),
+    ("Dagger", 8225),
+    ("bull", 8226),
+    ("hellip", 8230),
+    ("permil", 8240),
+    ("lsaquo", 8249),
+    ("rsaquo", 8250),
+    ("euro", 8364),
+    ("trade", 8482),
+    ("larr", 8592),
+    ("uarr", 8593),
+    ("rarr", 8594),
+    ("darr", 8595),
+];
+
+// Rewrite a malformed XML document into one the parsers
+// accept; positions in the warnings refer to the input
+pub fn repair(data: &[u8], ftype: FeedType) -> (Vec<u8>, Vec<Warning>) {
+    let mut repair = Repair {
+        data,
+        ftype,
+        line_starts: std::iter::once(0)
+            .chain(
+                data.iter()
+                    .enumerate()
+                    .filter(|(_, b)| **b == b'\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect(),
+        out: Vec::with_capacity(data.len()),
+        stack: Vec::new(),
+        declared: Vec::new(),
+        warnings: Vec::new(),
+    };
+    repair.run();
+    (repair.out, repair.warnings)
+}
+
+struct Repair<'a> {
+    data: &'a [u8],
+    ftype: FeedType,
+    line_starts: Vec<usize>,
+    out: Vec<u8>,
+    // open elements: name, offset of the start tag in the
+    // input and in the output
+    stack: Vec<(String, usize, usize)>,
+    // entities declared in the internal DTD subset
+    declared: Vec<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Repair<'_> {
+    fn run(&mut self) {
+        let mut i = 0;
+        while i < self.data.len() {
+            match self.data[i] {
+                b'<' => match self.markup(i) {
+                    Some(next) => i = next,
+                    None => break,
+                },
+                b'&' => i = self.reference(i),
+                b => {
+                    self.out.push(b);
+                    i += 1;
+                }
+            }
+        }
+        self.close_all();
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[line - 1];
+        Position {
+            line,
+            column: String::from_utf8_lossy(&self.data[line_start..offset])
+                .chars()
+                .count()
+                + 1,
+        }
+    }
+
+    // Copy the markup starting at i and return the offset
+    // following it, None when the document is truncated
+    fn markup(&mut self, i: usize) -> Option<usize> {
+        let rest = &self.data[i..];
+        let end = if rest.starts_with(b"<!--") {
+            find(rest, b"-->")? + 3
+        } else if rest.starts_with(b"<![CDATA[") {
+            find(rest, b"]]>")? + 3
+        } else if rest.starts_with(b"<?") {
+            find(rest, b"?>")? + 2
+        } else if rest.starts_with(b"<!") {
+            let end = doctype_end(rest)?;
+            self.declare_entities(&rest[..end]);
+            end
+        } else if rest.starts_with(b"</") {
+            let end = find(rest, b">")?;
+            let name = String::from_utf8_lossy(&rest[2..end]).trim().to_string();
+            self.close(&name, i, end + 1);
+            return Some(i + end + 1);
+        } else if rest.len() > 1 && is_name_start(rest[1]) {
+            return self.start_tag(i);
+        } else if rest.len() == 1 {
+            return None;
+        } else {
+            self.out.extend_from_slice(b"&lt;");
+            self.warnings
+                .push(Warning::UnescapedLessThan(self.position(i)));
+            return Some(i + 1);
+        };
+        self.out.extend_from_slice(&rest[..end]);
+        Some(i + end)
+    }
+
+    fn declare_entities(&mut self, doctype: &[u8]) {
+        let mut rest = doctype;
+        while let Some(start) = find(rest, b"<!ENTITY") {
+            rest = &rest[start + 8..];
+            let name: Vec<u8> = rest
+                .iter()
+                .skip_while(|b| b.is_ascii_whitespace() || **b == b'%')
+                .take_while(|b| is_name_char(**b))
+                .copied()
+                .collect();
+            self.declared
+                .push(String::from_utf8_lossy(&name).into_owned());
+        }
+    }
+
+    fn start_tag(&mut self, i: usize) -> Option<usize> {
+        let data = self.data;
+        let mut quote: Option<u8> = None;
+        let mut end = None;
+        for (j, b) in data.iter().enumerate().skip(i + 1) {
+            match (quote, *b) {
+                (None, b'"') | (None, b'\'') => quote = Some(*b),
+                (None, b'>') => {
+                    end = Some(j);
+                    break;
+                }
+                (Some(q), b) if q == b => quote = None,
+                _ => {}
+            }
+        }
+        let end = end?;
+        let name_end = data[i + 1..end]
+            .iter()
+            .position(|b| b.is_ascii_whitespace() || *b == b'/')
+            .map(|p| i + 1 + p)
+            .unwrap_or(end);
+        let name = String::from_utf8_lossy(&data[i + 1..name_end]).into_owned();
+        let self_closing = data[end - 1] == b'/';
+        let out_start = self.out.len();
+        // attribute values may hold bare ampersands too,
+        // usually in URLs
+        let mut j = i;
+        let mut quote: Option<u8> = None;
+        while j <= end {
+            let b = data[j];
+            match (quote, b) {
+                (None, b'"') | (None, b'\'') => quote = Some(b),
+                (Some(q), b) if q == b => quote = None,
+                (Some(_), b'&') => {
+                    j = self.reference(j).min(end);
+                    continue;
+                }
+                (Some(_), b'<') => {
+                    self.out.extend_from_slice(b"&lt;");
+                    j += 1;
+                    continue;
+                }
+                (None, b'=') => {
+                    self.out.push(b);
+                    let name_end = j;
+                    j += 1;
+                    while j < end && data[j].is_ascii_whitespace() {
+                        self.out.push(data[j]);
+                        j += 1;
+                    }
+                    if j < end && data[j] != b'"' && data[j] != b'\'' {
+                        j = self.unquoted_value(i, name_end, j, end, self_closing);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            self.out.push(b);
+            j += 1;
+        }
+        if self_closing {
+            return Some(end + 1);
+        }
+        self.stack.push((name.clone(), i, out_start));
+        let action = match self.text_action(&name, &data[i..end]) {
+            Some(action) => action,
+            None => return Some(end + 1),
+        };
+        let close = format!("</{}>", name);
+        match find(&data[end + 1..], close.as_bytes()) {
+            Some(len) if has_markup(&data[end + 1..end + 1 + len]) => {
+                self.warnings.push(Warning::StrayMarkup {
+                    element: name,
+                    position: self.position(end + 1),
+                });
+                self.text(end + 1, end + 1 + len, action);
+                Some(end + 1 + len)
+            }
+            _ => Some(end + 1),
+        }
+    }
+
+    // Quote the attribute value starting at start, as HTML
+    // reads it: up to a space or the end of the tag
+    fn unquoted_value(
+        &mut self,
+        tag_start: usize,
+        name_end: usize,
+        start: usize,
+        end: usize,
+        self_closing: bool,
+    ) -> usize {
+        let data = self.data;
+        let mut value_end = data[start..end]
+            .iter()
+            .position(|b| b.is_ascii_whitespace())
+            .map_or(end, |p| start + p);
+        if value_end == end && self_closing && value_end > start + 1 {
+            value_end -= 1;
+        }
+        let before = data[tag_start..name_end].trim_ascii_end();
+        let name_start = before
+            .iter()
+            .rposition(|b| b.is_ascii_whitespace())
+            .map_or(0, |p| p + 1);
+        self.warnings.push(Warning::UnquotedAttribute {
+            name: String::from_utf8_lossy(&before[name_start..]).into_owned(),
+            position: self.position(start),
+        });
+        self.out.push(b'"');
+        let mut j = start;
+        while j < value_end {
+            match data[j] {
+                b'&' => {
+                    j = self.reference(j).min(value_end);
+                    continue;
+                }
+                b'"' => self.out.extend_from_slice(b"&quot;"),
+                b'<' => self.out.extend_from_slice(b"&lt;"),
+                b => self.out.push(b),
+            }
+            j += 1;
+        }
+        self.out.push(b'"');
+        value_end
+    }
+
+    fn text_action(&self, name: &str, tag: &[u8]) -> Option<TextAction> {
+        match self.ftype {
+            FeedType::Rss | FeedType::Rdf => match name {
+                "title" => Some(TextAction::Strip),
+                "description" | "content:encoded" => Some(TextAction::Escape),
+                _ => None,
+            },
+            FeedType::Atom => {
+                let local = name.rsplit(':').next().unwrap_or(name);
+                if !["title", "subtitle", "summary", "rights", "content"].contains(&local) {
+                    return None;
+                }
+                match attribute(tag, "type").as_deref() {
+                    None | Some("text") => Some(TextAction::Strip),
+                    Some("html") => Some(TextAction::Escape),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Write the content of a text element, removing the
+    // markup it holds
+    fn text(&mut self, start: usize, end: usize, action: TextAction) {
+        let data = self.data;
+        let mut i = start;
+        while i < end {
+            let rest = &data[i..end];
+            if rest.starts_with(b"<![CDATA[") {
+                let len = find(rest, b"]]>").map(|p| p + 3).unwrap_or(rest.len());
+                escape_into(&mut self.out, &rest[9..len.max(12) - 3]);
+                i += len;
+            } else if is_tag(rest) {
+                let len = find(rest, b">").map(|p| p + 1).unwrap_or(rest.len());
+                if action == TextAction::Escape {
+                    escape_into(&mut self.out, &rest[..len]);
+                }
+                i += len;
+            } else if rest[0] == b'<' {
+                self.out.extend_from_slice(b"&lt;");
+                self.warnings
+                    .push(Warning::UnescapedLessThan(self.position(i)));
+                i += 1;
+            } else if rest[0] == b'&' {
+                i = self.reference(i);
+            } else {
+                self.out.push(rest[0]);
+                i += 1;
+            }
+        }
+    }
+
+    // Copy the reference starting at i and return the
+    // offset following it
+    fn reference(&mut self, i: usize) -> usize {
+        let rest = &self.data[i + 1..];
+        if let Some(len) = rest.iter().take(32).position(|b| *b == b';') {
+            let name = &rest[..len];
+            let valid = match name.strip_prefix(b"#") {
+                Some(number) => match number.strip_prefix(b"x") {
+                    Some(hex) => !hex.is_empty() && hex.iter().all(u8::is_ascii_hexdigit),
+                    None => !number.is_empty() && number.iter().all(u8::is_ascii_digit),
+                },
+                None => {
+                    !name.is_empty()
+                        && is_name_start(name[0])
+                        && name.iter().all(|b| is_name_char(*b))
+                }
+            };
+            if valid {
+                let name = String::from_utf8_lossy(name).into_owned();
+                if name.starts_with('#')
+                    || PREDEFINED_ENTITIES.contains(&name.as_str())
+                    || self.declared.contains(&name)
+                {
+                    self.out.extend_from_slice(&self.data[i..i + len + 2]);
+                    return i + len + 2;
+                }
+                let code = HTML_ENTITIES
+                    .iter()
+                    .find(|(entity, _)| *entity == name)
+                    .map(|(_, code)| *code);
+                self.warnings.push(Warning::UndeclaredEntity {
+                    name,
+                    position: self.position(i),
+                    replaced: code.is_some(),
+                });
+                return match code {
+                    Some(code) => {
+                        self.out
+                            .extend_from_slice(format!("&#{};", code).as_bytes());
+                        i + len + 2
+                    }
+                    None => {
+                        self.out.extend_from_slice(b"&amp;");
+                        i + 1
+                    }
+                };
+            }
+        }
+        self.out.extend_from_slice(b"&amp;");
+        self.warnings
+            .push(Warning::UnescapedAmpersand(self.position(i)));
+        i + 1
+    }
+
+    fn close(&mut self, name: &str, i: usize, len: usize) {
+        match self.stack.iter().rposition(|(n, _, _)| n == name) {
+            Some(index) => {
+                while self.stack.len() > index + 1 {
+                    let (open, start, _) = self.stack.pop().unwrap_or_default();
+                    self.out
+                        .extend_from_slice(format!("</{}>", open).as_bytes());
+                    self.warnings.push(Warning::UnclosedElement {
+                        name: open,
+                        position: self.position(start),
+                    });
+                }
+                self.stack.pop();
+                self.out.extend_from_slice(&self.data[i..i + len]);
+            }
+            None => self.warnings.push(Warning::UnmatchedClosingTag {
+                name: name.into(),
+                position: self.position(i),
+            }),
+        }
+    }
+
+    // Close the elements left open by a truncated document,
+    // dropping the entry that was cut
+    fn close_all(&mut self) {
+        let entry = self.stack.iter().position(|(name, _, _)| {
+            let local = name.rsplit(':').next().unwrap_or(name);
+            match self.ftype {
+                FeedType::Atom => local == "entry",
+                FeedType::Auto => local == "entry" || local == "item",
+                _ => local == "item",
+            }
+        });
+        if let Some(index) = entry {
+            let (_, start, out_start) = self.stack[index];
+            self.out.truncate(out_start);
+            self.stack.truncate(index);
+            self.warnings.push(Warning::SkippedEntry {
+                position: Some(self.position(start)),
+                reason: "the document is truncated".into(),
+            });
+        }
+        while let Some((name, start, _)) = self.stack.pop() {
+            self.out
+                .extend_from_slice(format!("</{}>", name).as_bytes());
+            self.warnings.push(Warning::UnclosedElement {
+                name,
+                position: self.position(start),
+            });
+        }
+    }
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).position(|w| w == needle)
+}
+
+fn is_name_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b':' || b >= 0x80
+}
+
+fn is_name_char(b: u8) -> bool {
+    is_name_start(b) || b.is_ascii_digit() || b == b'-' || b == b'.'
+}
+
+fn is_tag(data: &[u8]) -> bool {
+    data.len() > 1 && data[0] == b'<' && (is_name_start(data[1]) || data[1] == b'/')
+}
+
+// Whether some text holds tags, outside of CDATA sections
+fn has_markup(data: &[u8]) -> bool {
+    let mut i = 0;
+    while i < data.len() {
+        if data[i..].starts_with(b"<![CDATA[") {
+            match find(&data[i..], b"]]>") {
+                Some(len) => i += len + 3,
+                None => return false,
+            }
+        } else if is_tag(&data[i..]) {
+            return true;
+        } else {
+            i += 1;
+        }
+    }
+    false
+}
+
+fn escape_into(out: &mut Vec<u8>, data: &[u8]) {
+    for b in data {
+        match b {
+            b'&' => out.extend_from_slice(b"&amp;"),
+            b'<' => out.extend_from_slice(b"&lt;"),
+            b'>' => out.extend_from_slice(b"&gt;"),
+            b => out.push(*b),
+        }
+    }
+}
+
+// Length of a doctype declaration, including its internal
+// subset
+fn doctype_end(data: &[u8]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, b) in data.iter().enumerate() {
+        match b {
+            b'[' => depth += 1,
+            b']' => depth -= 1,
+            b'>' if depth == 0 => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+// Value of an attribute in a raw start tag
+fn attribute(tag: &[u8], name: &str) -> Option<String> {
+    let tag = String::from_utf8_lossy(tag);
+    let start = tag.match_indices(name).map(|(i, _)| i).find(|i| {
+        tag[..*i].ends_with(char::is_whitespace)
+            && tag[i + name.len()..].trim_start().starts_with('=')
+    })?;
+    let rest = tag[start + name.len()..].trim_start()[1..].trim_start();
+    let quote = rest.chars().next().filter(|q| *q == '"' || *q == '\'')?;
+    let rest = &rest[1..];
+    rest.find(quote).map(|end| rest[..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &str, ftype: FeedType) -> (Feed, Vec<Warning>) {
+        read_from_slice(data.as_bytes(), ftype).unwrap()
+    }
+
+    #[test]
+    fn well_formed_unchanged() {
+        let data = br#"<?xml version="1.0"?>
+<!-- comment -->
+<rss version="2.0"><channel><title>A &amp; B</title><description><![CDATA[<p>x</p>]]></description></channel></rss>"#;
+        let (repaired, warnings) = repair(data, FeedType::Rss);
+        assert_eq!(repaired, data.to_vec());
+        assert!(warnings.is_empty());
+    }
+    #[test]
+    fn unescaped_ampersands() {
+        let data = r#"<rss version="2.0"><channel><title>Tom & Jerry</title>
+<link>https://example.org/?a=1&b=2</link>
+<item><enclosure url="https://example.org/a.mp3?x=1&y=2" length="1" type="audio/mpeg"/></item>
+</channel></rss>"#;
+        let (feed, warnings) = read(data, FeedType::Rss);
+        let Feed::Rss(channel) = feed else {
+            panic!("expected a RSS feed")
+        };
+        assert_eq!(channel.title, "Tom & Jerry");
+        assert_eq!(channel.link, "https://example.org/?a=1&b=2");
+        assert_eq!(
+            channel.items[0].enclosure.as_ref().unwrap().url,
+            "https://example.org/a.mp3?x=1&y=2"
+        );
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(
+            warnings[0],
+            Warning::UnescapedAmpersand(Position {
+                line: 1,
+                column: 40
+            })
+        );
+    }
+    #[test]
+    fn html_entities() {
+        let data = r#"<rss version="2.0"><channel><title>Caf&eacute;&nbsp;&bogus;</title></channel></rss>"#;
+        let (feed, warnings) = read(data, FeedType::Rss);
+        let Feed::Rss(channel) = feed else {
+            panic!("expected a RSS feed")
+        };
+        assert_eq!(channel.title, "Café\u{a0}&bogus;");
+        assert_eq!(warnings.len(), 3);
+        assert!(matches!(
+            &warnings[2],
+            Warning::UndeclaredEntity { name, replaced: false, .. } if name == "bogus"
+        ));
+    }
+    #[test]
+    fn declared_entities_kept() {
+        let data = br#"<!DOCTYPE rss [<!ENTITY custom "x">]><rss version="2.0"><channel><title>&custom;</title></channel></rss>"#;
+        let (repaired, warnings) = repair(data, FeedType::Rss);
+        assert_eq!(repaired, data.to_vec());
+        assert!(warnings.is_empty());
+    }
+    #[test]
+    fn stray_markup() {
+        let data = r#"<rss version="2.0"><channel><title>A <b>bold</b> title</title>
+<item><description>Some <p>html</p> & more</description></item>
+</channel></rss>"#;
+        let (feed, warnings) = read(data, FeedType::Rss);
+        let Feed::Rss(channel) = feed else {
+            panic!("expected a RSS feed")
+        };
+        assert_eq!(channel.title, "A bold title");
+        assert_eq!(
+            channel.items[0].description.as_deref(),
+            Some("Some <p>html</p> & more")
+        );
+        assert!(warnings.iter().any(
+            |w| matches!(w, Warning::StrayMarkup { element, .. } if element == "description")
+        ));
+    }
+    #[test]
+    fn atom_html_content() {
+        let data = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>T</title>
+<entry><title>E</title><content type="html">a <br> b</content></entry>
+<entry><title>X</title><content type="xhtml"><div xmlns="http://www.w3.org/1999/xhtml">ok</div></content></entry>
+</feed>"#;
+        let (feed, warnings) = read(data, FeedType::Atom);
+        let Feed::Atom(feed) = feed else {
+            panic!("expected an Atom feed")
+        };
+        assert_eq!(
+            feed.entries[0].content.as_ref().unwrap().value.as_deref(),
+            Some("a <br> b")
+        );
+        assert_eq!(warnings.len(), 1);
+    }
+    #[test]
+    fn mismatched_tags() {
+        let data = br#"<rss version="2.0"><channel><item><guid>1</guid></span></item><image><url>u</url></channel></rss>"#;
+        let (repaired, warnings) = repair(data, FeedType::Rss);
+        assert_eq!(
+            String::from_utf8(repaired).unwrap(),
+            r#"<rss version="2.0"><channel><item><guid>1</guid></item><image><url>u</url></image></channel></rss>"#
+        );
+        assert!(
+            matches!(&warnings[0], Warning::UnmatchedClosingTag { name, .. } if name == "span")
+        );
+        assert!(matches!(&warnings[1], Warning::UnclosedElement { name, .. } if name == "image"));
+    }
+    #[test]
+    fn truncated_document() {
+        let data = r#"<rss version="2.0"><channel><title>T</title>
+<item><title>One</title></item>
+<item><title>Two</title><descrip"#;
+        let (feed, warnings) = read(data, FeedType::Auto);
+        let Feed::Rss(channel) = feed else {
+            panic!("expected a RSS feed")
+        };
+        assert_eq!(channel.items.len(), 1);
+        assert_eq!(channel.items[0].title.as_deref(), Some("One"));
+        assert_eq!(
+            warnings[0],
+            Warning::SkippedEntry {
+                position: Some(Position { line: 3, column: 1 }),
+                reason: "the document is truncated".into()
+            }
+        );
+        assert_eq!(warnings.len(), 3);
+    }
+    #[test]
+    fn atom_invalid_dates() {
+        let data = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>T</title>
+<updated>Mon, 01 Jan 2024 10:00:00 GMT</updated>
+<entry><title>E</title><updated>yesterday</updated></entry>
+</feed>"#;
+        let (feed, warnings) = read(data, FeedType::Atom);
+        let Feed::Atom(feed) = feed else {
+            panic!("expected an Atom feed")
+        };
+        assert_eq!(feed.updated.to_rfc3339(), "2024-01-01T10:00:00+00:00");
+        assert_eq!(feed.entries.len(), 1);
+        assert_eq!(
+            warnings,
+            vec![Warning::InvalidDate {
+                value: "yesterday".into(),
+                replacement: None
+            }]
+        );
+    }
+    #[test]
+    fn invalid_date_same_text_as_title() {
+        let data = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>yesterday</title>
+<entry><title>yesterday</title><summary>yesterday</summary>
+<updated> yesterday </updated></entry>
+</feed>"#;
+        let (feed, warnings) = read(data, FeedType::Atom);
+        let Feed::Atom(feed) = feed else {
+            panic!("expected an Atom feed")
+        };
+        assert_eq!(feed.title.value, "yesterday");
+        assert_eq!(feed.entries[0].title.value, "yesterday");
+        assert_eq!(
+            feed.entries[0].summary.as_ref().map(|s| s.value.as_str()),
+            Some("yesterday")
+        );
+        assert_eq!(warnings.len(), 1);
+    }
+    #[test]
+    fn unquoted_non_ascii_attribute() {
+        let data =
+            r#"<feed xmlns="http://www.w3.org/2005/Atom"><title type=éx>x <b>y</b></title></feed>"#;
+        let (repaired, warnings) = repair(data.as_bytes(), FeedType::Atom);
+        let repaired = String::from_utf8(repaired).unwrap();
+        assert!(repaired.contains("type=\"éx\""), "{}", repaired);
+        assert!(matches!(
+            &warnings[0],
+            Warning::UnquotedAttribute { name, .. } if name == "type"
+        ));
+        let data = r#"<rss version="2.0"><channel><link href=a&b/></channel></rss>"#;
+        let (repaired, _) = repair(data.as_bytes(), FeedType::Rss);
+        assert_eq!(
+            String::from_utf8(repaired).unwrap(),
+            r#"<rss version="2.0"><channel><link href="a&amp;b"/></channel></rss>"#
+        );
+    }
+    #[test]
+    fn json_skipped_items() {
+        let data = r#"{"version": "https://jsonfeed.org/version/1.1", "title": "T",
+            "items": [{"id": "1"}, {"title": "no id"}, {"id": "3"}]}"#;
+        let (feed, warnings) = read(data, FeedType::Auto);
+        let Feed::Json(feed) = feed else {
+            panic!("expected a JSON feed")
+        };
+        assert_eq!(feed.items.len(), 2);
+        assert_eq!(warnings.len(), 1);
+    }
+}
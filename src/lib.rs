@@ -1,2 +1,22 @@
 pub mod abstractions;
+pub mod aggregate;
+pub mod date;
+pub mod detect;
+pub mod diff;
+pub mod discovery;
+pub mod encoding;
+pub mod error;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+pub mod jsonfeed;
+pub mod lenient;
 pub mod models;
+pub mod paging;
+pub mod query;
+pub mod rdf;
+pub mod schedule;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(feature = "websub")]
+pub mod websub;
+mod xml;
@@ -1,28 +1,131 @@
-use atom_syndication::{Category as AtomCategory, Entry as AtomItem, Feed as AtomFeed};
-use rss::{Category as RssCategory, Channel as RssFeed, Item as RssItem};
-use std::error::Error;
-use std::io::BufRead;
+use crate::abstractions::feed::Feed as AbstractFeed;
+use crate::detect::detect;
+use crate::encoding::{self, DetectedEncoding};
+use crate::error::Error;
+use crate::jsonfeed::Feed as JsonFeed;
+use crate::lenient::{self, Warning};
+use crate::rdf;
+use atom_syndication::Feed as AtomFeed;
+use atom_syndication::WriteConfig as AtomWriteConfig;
+use rss::Channel as RssFeed;
+use std::io::{BufRead, Write};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FeedType {
     Rss,
     Atom,
+    Rdf,
+    Json,
+    // Sniff the document to find its type
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WriteConfig {
+    pub write_document_declaration: bool,
+    // None writes the whole document on one line
+    pub indent_size: Option<usize>,
+    pub indent_char: u8,
+}
+
+impl Default for WriteConfig {
+    fn default() -> Self {
+        Self {
+            write_document_declaration: true,
+            indent_size: None,
+            indent_char: b' ',
+        }
+    }
+}
+
+impl WriteConfig {
+    pub fn pretty(indent_size: usize) -> Self {
+        Self {
+            indent_size: Some(indent_size),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum FeedUrl {
     Rss(String),
     Atom(String),
+    Rdf(String),
+    Json(String),
+}
+
+impl FeedUrl {
+    pub fn url(&self) -> &str {
+        match self {
+            FeedUrl::Rss(url) | FeedUrl::Atom(url) | FeedUrl::Rdf(url) | FeedUrl::Json(url) => url,
+        }
+    }
+
+    pub fn feed_type(&self) -> FeedType {
+        match self {
+            FeedUrl::Rss(_) => FeedType::Rss,
+            FeedUrl::Atom(_) => FeedType::Atom,
+            FeedUrl::Rdf(_) => FeedType::Rdf,
+            FeedUrl::Json(_) => FeedType::Json,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Feed {
     Atom(AtomFeed),
     Rss(RssFeed),
+    Rdf(RssFeed),
+    Json(JsonFeed),
 }
 
 impl Feed {
-    pub fn read_from<R: BufRead>(data: R, ftype: FeedType) -> Result<Feed, Box<dyn Error>> {
+    pub fn read_from<R: BufRead>(data: R, ftype: FeedType) -> Result<Feed, Error> {
+        Feed::read_with_encoding(data, ftype, None).map(|(feed, _)| feed)
+    }
+
+    // The document is transcoded to UTF-8 first; hint is a
+    // charset label or a Content-Type value, usually from HTTP
+    pub fn read_with_encoding<R: BufRead>(
+        data: R,
+        ftype: FeedType,
+        hint: Option<&str>,
+    ) -> Result<(Feed, DetectedEncoding), Error> {
+        let mut data = data;
+        let mut buf: Vec<u8> = Vec::new();
+        data.read_to_end(&mut buf)?;
+        let (buf, detected) = encoding::decode(&buf, hint);
+        // The document is kept to locate syntax errors
+        let feed = Feed::read_from_slice(&buf, ftype).map_err(|err| err.with_document(&buf))?;
+        Ok((feed, detected))
+    }
+
+    // Repair common breakage instead of failing, what was
+    // fixed or dropped is listed in the warnings
+    pub fn read_lenient<R: BufRead>(
+        data: R,
+        ftype: FeedType,
+    ) -> Result<(Feed, Vec<Warning>), Error> {
+        Feed::read_lenient_with_encoding(data, ftype, None)
+            .map(|(feed, warnings, _)| (feed, warnings))
+    }
+
+    pub fn read_lenient_with_encoding<R: BufRead>(
+        data: R,
+        ftype: FeedType,
+        hint: Option<&str>,
+    ) -> Result<(Feed, Vec<Warning>, DetectedEncoding), Error> {
+        let mut data = data;
+        let mut buf: Vec<u8> = Vec::new();
+        data.read_to_end(&mut buf)?;
+        let (buf, detected) = encoding::decode(&buf, hint);
+        let (feed, warnings) = lenient::read_from_slice(&buf, ftype)?;
+        Ok((feed, warnings, detected))
+    }
+
+    fn read_from_slice(data: &[u8], ftype: FeedType) -> Result<Feed, Error> {
         match ftype {
             FeedType::Rss => {
                 let feed = RssFeed::read_from(data)?;
@@ -32,107 +135,222 @@ impl Feed {
                 let feed = AtomFeed::read_from(data)?;
                 Ok(Feed::Atom(feed))
             }
+            FeedType::Rdf => {
+                let feed = rdf::read_from(data)?;
+                Ok(Feed::Rdf(feed))
+            }
+            FeedType::Json => {
+                let feed = JsonFeed::read_from(data)?;
+                Ok(Feed::Json(feed))
+            }
+            FeedType::Auto => match detect(data) {
+                Some(ftype) => Feed::read_from_slice(data, ftype),
+                None => Err(Error::UnknownFormat),
+            },
         }
     }
-}
 
-#[derive(Debug, Clone, PartialEq)]
-struct Category {
-    name: String,
-    description: String,
-}
-
-impl From<RssCategory> for Category {
-    fn from(item: RssCategory) -> Self {
-        Self {
-            name: item.name,
-            description: "".to_string(),
+    // Build a feed of the given type from an abstract feed
+    pub fn from_abstract(feed: AbstractFeed, ftype: FeedType) -> Result<Feed, Error> {
+        match ftype {
+            FeedType::Rss => Ok(Feed::Rss(feed.into())),
+            FeedType::Atom => Ok(Feed::Atom(feed.into())),
+            FeedType::Rdf => Ok(Feed::Rdf(feed.into())),
+            FeedType::Json => Ok(Feed::Json(feed.into())),
+            FeedType::Auto => Err(Error::UnsupportedFeedType(ftype)),
         }
     }
-}
 
-impl From<AtomCategory> for Category {
-    fn from(item: AtomCategory) -> Self {
-        Self {
-            name: item.term,
-            description: "".to_string(),
+    pub fn write_to<W: Write>(&self, writer: W, ftype: FeedType) -> Result<W, Error> {
+        self.write_with_config(writer, ftype, &WriteConfig::default())
+    }
+
+    // FeedType::Auto writes the feed in its own format,
+    // any other type converts it through the abstractions
+    pub fn write_with_config<W: Write>(
+        &self,
+        writer: W,
+        ftype: FeedType,
+        config: &WriteConfig,
+    ) -> Result<W, Error> {
+        if ftype != FeedType::Auto && ftype != self.feed_type() {
+            let feed = Feed::from_abstract(self.clone().into(), ftype)?;
+            return feed.write_with_config(writer, FeedType::Auto, config);
+        }
+        match self {
+            Feed::Atom(feed) => {
+                let atom_config = AtomWriteConfig {
+                    write_document_declaration: config.write_document_declaration,
+                    indent_size: config.indent_size,
+                };
+                Ok(feed.write_with_config(writer, atom_config)?)
+            }
+            Feed::Rss(feed) => {
+                let buf = match config.indent_size {
+                    Some(size) => feed.pretty_write_to(Vec::new(), config.indent_char, size)?,
+                    None => feed.write_to(Vec::new())?,
+                };
+                // rss always writes the XML declaration
+                let mut buf: &[u8] = &buf;
+                if !config.write_document_declaration {
+                    if let Some(end) = buf.windows(2).position(|w| w == b"?>") {
+                        buf = buf[end + 2..].trim_ascii_start();
+                    }
+                }
+                let mut writer = writer;
+                writer.write_all(buf)?;
+                Ok(writer)
+            }
+            Feed::Rdf(feed) => rdf::write_with_config(feed, writer, config),
+            Feed::Json(feed) => match config.indent_size {
+                Some(size) => feed.pretty_write_to(writer, config.indent_char, size),
+                None => feed.write_to(writer),
+            },
         }
     }
-}
 
-#[derive(Debug, Clone, PartialEq)]
-struct Article {
-    uuid: String,
-    date: String,
-    title: String,
-    categories: Vec<Category>,
-    authors: Vec<String>,
-    content: String,
-    summary: String,
-    link: String,
+    pub fn feed_type(&self) -> FeedType {
+        match self {
+            Feed::Atom(_) => FeedType::Atom,
+            Feed::Rss(_) => FeedType::Rss,
+            Feed::Rdf(_) => FeedType::Rdf,
+            Feed::Json(_) => FeedType::Json,
+        }
+    }
 }
 
-impl From<RssItem> for Article {
-    fn from(item: RssItem) -> Self {
-        let title = item.title().get_or_insert("").to_string();
-        let date = item.pub_date().get_or_insert("").to_string();
-        let content = item.content().get_or_insert("").to_string();
-        let link = item.link().get_or_insert("").to_string();
-        let mut categories: Vec<Category> = Vec::new();
-        for c in item.categories {
-            categories.push(c.into());
-        }
-        let mut authors: Vec<String> = Vec::new();
-        if let Some(au) = item.author {
-            authors.push(au);
-        }
-        Self {
-            uuid: "a".to_string(),
-            date,
-            title,
-            content,
-            categories,
-            authors,
-            // no summary in rss
-            // may be show the begining of content
-            summary: "".to_string(),
-            link,
+impl From<Feed> for AbstractFeed {
+    fn from(value: Feed) -> Self {
+        match value {
+            Feed::Atom(feed) => feed.into(),
+            Feed::Rss(feed) => feed.into(),
+            Feed::Rdf(feed) => feed.into(),
+            Feed::Json(feed) => feed.into(),
         }
     }
 }
 
-impl From<AtomItem> for Article {
-    fn from(item: AtomItem) -> Self {
-        let mut content: String = String::from("");
-        if let Some(con) = item.content {
-            if let Some(value) = con.value() {
-                content = value.to_string();
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_auto_rss() {
+        let data = br#"<rss version="2.0"><channel><title>Title</title></channel></rss>"#;
+        let feed = Feed::read_from(&data[..], FeedType::Auto).unwrap();
+        assert_eq!(feed.feed_type(), FeedType::Rss);
+    }
+    #[test]
+    fn read_auto_atom() {
+        let data = br#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Title</title></feed>"#;
+        let feed = Feed::read_from(&data[..], FeedType::Auto).unwrap();
+        assert_eq!(feed.feed_type(), FeedType::Atom);
+    }
+    #[test]
+    fn read_auto_rdf() {
+        let data = crate::rdf::tests::FEED.as_bytes();
+        let feed = Feed::read_from(data, FeedType::Auto).unwrap();
+        assert_eq!(feed.feed_type(), FeedType::Rdf);
+    }
+    #[test]
+    fn read_auto_json() {
+        let data =
+            br#"{"version": "https://jsonfeed.org/version/1.1", "title": "Title", "items": []}"#;
+        let feed = Feed::read_from(&data[..], FeedType::Auto).unwrap();
+        assert_eq!(feed.feed_type(), FeedType::Json);
+    }
+    #[test]
+    fn write_converted() {
+        let data = br#"<rss version="2.0"><channel><title>Title</title></channel></rss>"#;
+        let feed = Feed::read_from(&data[..], FeedType::Rss).unwrap();
+        for ftype in [FeedType::Atom, FeedType::Rdf, FeedType::Json] {
+            let buf = feed.write_to(Vec::new(), ftype).unwrap();
+            let feed2 = Feed::read_from(&buf[..], FeedType::Auto).unwrap();
+            assert_eq!(feed2.feed_type(), ftype);
+            let feed2: AbstractFeed = feed2.into();
+            assert_eq!(feed2.title.value, "Title");
         }
-        let mut summary: String = String::from("");
-        if let Some(text) = item.summary {
-            summary = text.value;
+    }
+    #[test]
+    fn write_auto_keeps_type() {
+        let data = br#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Title</title></feed>"#;
+        let feed = Feed::read_from(&data[..], FeedType::Atom).unwrap();
+        let buf = feed.write_to(Vec::new(), FeedType::Auto).unwrap();
+        let feed2 = Feed::read_from(&buf[..], FeedType::Auto).unwrap();
+        assert_eq!(feed, feed2);
+    }
+    #[test]
+    fn write_config() {
+        let data = br#"<rss version="2.0"><channel><title>Title</title></channel></rss>"#;
+        let feed = Feed::read_from(&data[..], FeedType::Rss).unwrap();
+        let config = WriteConfig {
+            write_document_declaration: false,
+            ..WriteConfig::pretty(2)
         };
-        let mut categories: Vec<Category> = Vec::new();
-        for c in item.categories {
-            categories.push(c.into());
-        }
-        Self {
-            uuid: item.id.clone(),
-            date: item.updated.to_string(),
-            title: item.title.to_string(),
-            categories,
-            content,
-            authors: vec![],
-            summary,
-            link: item.id,
+        for ftype in [FeedType::Rss, FeedType::Atom, FeedType::Rdf] {
+            let buf = feed.write_with_config(Vec::new(), ftype, &config).unwrap();
+            let text = String::from_utf8(buf).unwrap();
+            assert!(!text.starts_with("<?xml"));
+            assert!(text.contains("\n  <"));
         }
     }
-}
-
-#[derive(Debug, Clone, PartialEq)]
-struct ArticleFeed {
-    name: String,
-    articles: Vec<Article>,
-    source: String,
+    #[test]
+    fn from_abstract_auto() {
+        let err = Feed::from_abstract(AbstractFeed::default(), FeedType::Auto).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeedType(FeedType::Auto)));
+    }
+    #[test]
+    fn read_auto_unknown() {
+        let data = b"<html></html>";
+        let err = Feed::read_from(&data[..], FeedType::Auto).unwrap_err();
+        assert!(matches!(err, Error::UnknownFormat));
+    }
+    #[test]
+    fn read_syntax_error_position() {
+        let data = b"<rss version=\"2.0\">\n<channel>\n<title>T</titel>\n</channel></rss>";
+        let err = Feed::read_from(&data[..], FeedType::Rss).unwrap_err();
+        assert!(matches!(err, Error::Xml { .. }));
+        assert_eq!(err.position().map(|p| p.line), Some(3));
+    }
+    #[test]
+    fn read_lenient_repairs() {
+        let data = b"<rss version=\"2.0\"><channel><title>A & B</title></channel></rss>";
+        assert!(Feed::read_from(&data[..], FeedType::Auto).is_err());
+        let (feed, warnings) = Feed::read_lenient(&data[..], FeedType::Auto).unwrap();
+        assert_eq!(feed.feed_type(), FeedType::Rss);
+        assert_eq!(warnings.len(), 1);
+    }
+    #[test]
+    fn read_lenient_encoding_hint() {
+        let data = b"<rss version=\"2.0\"><channel><title>\xE1 & \xE2</title></channel></rss>";
+        let (feed, warnings, detected) = Feed::read_lenient_with_encoding(
+            &data[..],
+            FeedType::Auto,
+            Some("text/xml; charset=koi8-r"),
+        )
+        .unwrap();
+        assert_eq!(detected.name(), "KOI8-R");
+        assert_eq!(warnings.len(), 1);
+        let Feed::Rss(channel) = feed else {
+            panic!("expected a RSS feed")
+        };
+        assert_eq!(channel.title, "\u{410} & \u{411}");
+    }
+    #[test]
+    fn read_latin1() {
+        let data = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>
+<rss version=\"2.0\"><channel><title>Caf\xE9</title></channel></rss>";
+        let (feed, detected) = Feed::read_with_encoding(&data[..], FeedType::Auto, None).unwrap();
+        assert_eq!(detected.name(), "windows-1252");
+        let Feed::Rss(channel) = feed else {
+            panic!("expected a RSS feed")
+        };
+        assert_eq!(channel.title, "Café");
+    }
+    #[test]
+    fn read_wrong_type() {
+        let data = br#"<feed xmlns="http://www.w3.org/2005/Atom"></feed>"#;
+        let err = Feed::read_from(&data[..], FeedType::Rss).unwrap_err();
+        assert!(matches!(err, Error::InvalidFeed(_)));
+    }
 }
@@ -0,0 +1,583 @@
+use crate::abstractions::{
+    entry::Entry, extension::Extension, feed::Feed, fingerprint::Fingerprint, link::Link,
+};
+use crate::error::Error;
+use crate::models::{FeedType, WriteConfig};
+use std::collections::{HashSet, VecDeque};
+use url::Url;
+
+// Feed history, see https://www.rfc-editor.org/rfc/rfc5005
+pub const HISTORY_NAMESPACE: &str = "http://purl.org/syndication/history/1.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paging {
+    // fh:complete, the document holds every entry
+    Complete,
+    // fh:archive, a page that will not change anymore
+    Archived,
+    // the subscription document of an archived feed, older
+    // entries are on the pages linked with prev-archive
+    Subscription,
+    // pages linked with first, next, previous and last
+    Paged,
+    // no paging information, older entries may be gone
+    None,
+}
+
+// Navigation links of a feed, resolved against its self link
+// or base
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageLinks {
+    pub first: Option<String>,
+    pub last: Option<String>,
+    pub next: Option<String>,
+    pub previous: Option<String>,
+    pub current: Option<String>,
+    pub prev_archive: Option<String>,
+    pub next_archive: Option<String>,
+}
+
+impl Feed {
+    pub fn paging(&self) -> Paging {
+        if self.has_history_element("complete") {
+            Paging::Complete
+        } else if self.has_history_element("archive") {
+            Paging::Archived
+        } else {
+            let links = self.page_links();
+            let history = [&links.prev_archive, &links.next_archive, &links.current];
+            let paged = [&links.first, &links.last, &links.next, &links.previous];
+            if history.iter().any(|l| l.is_some()) {
+                Paging::Subscription
+            } else if paged.iter().any(|l| l.is_some()) {
+                Paging::Paged
+            } else {
+                Paging::None
+            }
+        }
+    }
+
+    pub fn page_links(&self) -> PageLinks {
+        let base = self
+            .links
+            .iter()
+            .find(|l| l.rel == "self")
+            .map(|l| l.href.as_str())
+            .or(self.base.as_deref())
+            .and_then(|b| Url::parse(b).ok());
+        let find = |rels: &[&str]| {
+            let link = self.links.iter().find(|l| rels.contains(&l.rel.as_str()))?;
+            match base.as_ref().and_then(|b| b.join(&link.href).ok()) {
+                Some(url) => Some(url.to_string()),
+                None => Some(link.href.clone()),
+            }
+        };
+        PageLinks {
+            first: find(&["first"]),
+            last: find(&["last"]),
+            next: find(&["next"]),
+            previous: find(&["previous", "prev"]),
+            current: find(&["current"]),
+            prev_archive: find(&["prev-archive"]),
+            next_archive: find(&["next-archive"]),
+        }
+    }
+
+    fn has_history_element(&self, name: &str) -> bool {
+        let prefix = self
+            .namespaces
+            .iter()
+            .find(|(_, ns)| ns.as_str() == HISTORY_NAMESPACE)
+            .map(|(prefix, _)| prefix.as_str())
+            .unwrap_or("fh");
+        self.extensions
+            .get(prefix)
+            .is_some_and(|ext| ext.contains_key(name))
+    }
+}
+
+// Source of the pages, usually an HTTP client
+pub trait Fetcher {
+    fn fetch(&mut self, url: &str) -> Result<Feed, Error>;
+}
+
+impl<F> Fetcher for F
+where
+    F: FnMut(&str) -> Result<Feed, Error>,
+{
+    fn fetch(&mut self, url: &str) -> Result<Feed, Error> {
+        self(url)
+    }
+}
+
+// Entries of a feed and of the pages it links to: the
+// prev-archive chain for archived feeds, the next chain for
+// paged ones. Entries already seen on a previous page are
+// skipped, and an error ends the iteration.
+pub struct History<F: Fetcher> {
+    fetcher: F,
+    entries: VecDeque<Entry>,
+    next_page: Option<String>,
+    visited: HashSet<String>,
+    seen: HashSet<Fingerprint>,
+    pages_left: Option<usize>,
+    failed: bool,
+}
+
+impl<F: Fetcher> History<F> {
+    pub fn new(feed: Feed, fetcher: F) -> Self {
+        let mut history = Self {
+            fetcher,
+            entries: VecDeque::new(),
+            next_page: None,
+            visited: HashSet::new(),
+            seen: HashSet::new(),
+            pages_left: None,
+            failed: false,
+        };
+        history.add_page(feed);
+        history
+    }
+
+    // Stop after fetching this many pages
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.pages_left = Some(max_pages);
+        self
+    }
+
+    fn add_page(&mut self, feed: Feed) {
+        if let Some(link) = feed.links.iter().find(|l| l.rel == "self") {
+            self.visited.insert(link.href.clone());
+        }
+        let links = feed.page_links();
+        self.next_page = match feed.paging() {
+            Paging::Complete => None,
+            _ => links.prev_archive.or(links.next),
+        }
+        .filter(|url| !self.visited.contains(url));
+        for entry in feed.entries {
+            if self.seen.insert(entry.fingerprint()) {
+                self.entries.push_back(entry);
+            }
+        }
+    }
+}
+
+impl<F: Fetcher> Iterator for History<F> {
+    type Item = Result<Entry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.entries.pop_front() {
+                return Some(Ok(entry));
+            }
+            if self.failed || self.pages_left == Some(0) {
+                return None;
+            }
+            let url = self.next_page.take()?;
+            self.visited.insert(url.clone());
+            if let Some(left) = self.pages_left.as_mut() {
+                *left -= 1;
+            }
+            match self.fetcher.fetch(&url) {
+                Ok(feed) => self.add_page(feed),
+                Err(err) => {
+                    self.failed = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PageStyle {
+    // A current feed and immutable archives numbered from
+    // the oldest, linked with prev-archive
+    #[default]
+    Archive,
+    // Pages numbered from the newest, linked with next
+    Paged,
+}
+
+#[derive(Debug, Clone)]
+pub struct PageConfig {
+    pub page_size: usize,
+    pub style: PageStyle,
+    // URL of the subscription document
+    pub current_url: String,
+    // URL of the other pages, "{}" is replaced by the page
+    // number
+    pub page_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub url: String,
+    pub feed: Feed,
+}
+
+// A written page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageFile {
+    pub url: String,
+    pub data: Vec<u8>,
+}
+
+// Split entries, newest first, into pages carrying the
+// metadata of the template. The current page comes first.
+// Archives only hold full pages so that they never change,
+// the current feed keeps between page_size and twice as
+// many entries.
+pub fn paginate(template: &Feed, entries: &[Entry], config: &PageConfig) -> Vec<Page> {
+    let size = config.page_size.max(1);
+    let page_url = |number: usize| config.page_url.replace("{}", &number.to_string());
+    let chunks: Vec<&[Entry]> = match config.style {
+        PageStyle::Archive => {
+            let archived = entries.len().saturating_sub(size) / size * size;
+            let (current, archives) = entries.split_at(entries.len() - archived);
+            // archives are numbered from the oldest
+            let mut chunks: Vec<&[Entry]> = archives.rchunks(size).collect();
+            chunks.push(current);
+            chunks.reverse();
+            chunks
+        }
+        PageStyle::Paged if entries.is_empty() => vec![entries],
+        PageStyle::Paged => entries.chunks(size).collect(),
+    };
+    let count = chunks.len();
+    let url = |i: usize| match (i, config.style) {
+        (0, _) => config.current_url.clone(),
+        (_, PageStyle::Archive) => page_url(count - i),
+        (_, PageStyle::Paged) => page_url(i + 1),
+    };
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut links: Vec<(&str, String)> = vec![("self", url(i))];
+            let mut marker = None;
+            match config.style {
+                PageStyle::Archive => {
+                    if i > 0 {
+                        links.push(("current", url(0)));
+                        marker = Some("archive");
+                    }
+                    if i + 1 < count {
+                        links.push(("prev-archive", url(i + 1)));
+                    }
+                    if i > 1 {
+                        links.push(("next-archive", url(i - 1)));
+                    }
+                }
+                PageStyle::Paged => {
+                    links.push(("first", url(0)));
+                    links.push(("last", url(count - 1)));
+                    if i > 0 {
+                        links.push(("previous", url(i - 1)));
+                    }
+                    if i + 1 < count {
+                        links.push(("next", url(i + 1)));
+                    }
+                }
+            }
+            if count == 1 {
+                marker = Some("complete");
+            }
+            Page {
+                url: url(i),
+                feed: page_feed(template, chunk, &links, marker, i > 0),
+            }
+        })
+        .collect()
+}
+
+pub fn write_pages(
+    pages: &[Page],
+    ftype: FeedType,
+    config: &WriteConfig,
+) -> Result<Vec<PageFile>, Error> {
+    pages
+        .iter()
+        .map(|page| {
+            Ok(PageFile {
+                url: page.url.clone(),
+                data: page.feed.write_with_config(Vec::new(), ftype, config)?,
+            })
+        })
+        .collect()
+}
+
+const PAGE_RELS: [&str; 8] = [
+    "self",
+    "first",
+    "last",
+    "next",
+    "previous",
+    "current",
+    "prev-archive",
+    "next-archive",
+];
+
+fn page_feed(
+    template: &Feed,
+    entries: &[Entry],
+    links: &[(&str, String)],
+    marker: Option<&str>,
+    dated_by_entries: bool,
+) -> Feed {
+    let mut feed = template.clone();
+    feed.entries = entries.to_vec();
+    feed.links.retain(|l| !PAGE_RELS.contains(&l.rel.as_str()));
+    feed.links.extend(links.iter().map(|(rel, href)| Link {
+        href: href.clone(),
+        rel: rel.to_string(),
+        ..Default::default()
+    }));
+    let prefix = feed
+        .namespaces
+        .iter()
+        .find(|(_, ns)| ns.as_str() == HISTORY_NAMESPACE)
+        .map(|(prefix, _)| prefix.clone())
+        .unwrap_or_else(|| "fh".into());
+    if let Some(ext) = feed.extensions.get_mut(&prefix) {
+        ext.remove("archive");
+        ext.remove("complete");
+    }
+    if let Some(marker) = marker {
+        feed.namespaces
+            .insert(prefix.clone(), HISTORY_NAMESPACE.into());
+        feed.extensions.entry(prefix.clone()).or_default().insert(
+            marker.into(),
+            vec![Extension {
+                name: format!("{}:{}", prefix, marker),
+                ..Default::default()
+            }],
+        );
+    }
+    // an archive does not change with the template
+    if dated_by_entries {
+        let newest = entries.iter().filter_map(Entry::updated_date).max();
+        feed.updated = newest.or(feed.updated);
+    }
+    feed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Feed as ModelFeed;
+    use chrono::{DateTime, Duration};
+    use std::{collections::HashMap, str::FromStr};
+
+    fn page(links: &str, extra: &str, ids: &[&str]) -> Feed {
+        let entries: String = ids
+            .iter()
+            .map(|id| {
+                format!(
+                    "<entry><title>{0}</title><id>{0}</id><updated>2024-01-01T00:00:00Z</updated></entry>",
+                    id
+                )
+            })
+            .collect();
+        let data = format!(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:fh="http://purl.org/syndication/history/1.0">
+<title>T</title><id>urn:feed</id><updated>2024-01-01T00:00:00Z</updated>{}{}{}</feed>"#,
+            links, extra, entries
+        );
+        ModelFeed::read_from(data.as_bytes(), FeedType::Atom)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn recognize_paging() {
+        let complete = page("", "<fh:complete/>", &[]);
+        assert_eq!(complete.paging(), Paging::Complete);
+        let archive = page(
+            r#"<link rel="current" href="/feed"/><link rel="self" href="https://example.org/2023"/>"#,
+            "<fh:archive/>",
+            &[],
+        );
+        assert_eq!(archive.paging(), Paging::Archived);
+        assert_eq!(
+            archive.page_links().current.as_deref(),
+            Some("https://example.org/feed")
+        );
+        let paged = page(
+            r#"<link rel="next" href="https://example.org/2"/>"#,
+            "",
+            &[],
+        );
+        assert_eq!(paged.paging(), Paging::Paged);
+        let subscription = page(
+            r#"<link rel="prev-archive" href="https://example.org/2023"/>"#,
+            "",
+            &[],
+        );
+        assert_eq!(subscription.paging(), Paging::Subscription);
+        assert_eq!(page("", "", &[]).paging(), Paging::None);
+    }
+    #[test]
+    fn follow_archives() {
+        let current = page(
+            r#"<link rel="self" href="https://example.org/feed"/><link rel="prev-archive" href="/2023"/>"#,
+            "",
+            &["urn:3", "urn:2"],
+        );
+        let mut pages: HashMap<String, Feed> = HashMap::new();
+        pages.insert(
+            "https://example.org/2023".into(),
+            page(
+                r#"<link rel="self" href="https://example.org/2023"/><link rel="prev-archive" href="/2022"/>"#,
+                "<fh:archive/>",
+                &["urn:2", "urn:1"],
+            ),
+        );
+        pages.insert(
+            "https://example.org/2022".into(),
+            page(
+                // loops back, must not be fetched twice
+                r#"<link rel="self" href="https://example.org/2022"/><link rel="prev-archive" href="/feed"/>"#,
+                "<fh:archive/>",
+                &["urn:0"],
+            ),
+        );
+        let mut fetched: Vec<String> = Vec::new();
+        let fetcher = |url: &str| {
+            fetched.push(url.to_string());
+            pages
+                .get(url)
+                .cloned()
+                .ok_or_else(|| Error::InvalidFeed(url.to_string()))
+        };
+        let ids: Vec<String> = History::new(current, fetcher)
+            .map(|e| e.unwrap().guid.value)
+            .collect();
+        assert_eq!(ids, vec!["urn:3", "urn:2", "urn:1", "urn:0"]);
+        assert_eq!(
+            fetched,
+            vec!["https://example.org/2023", "https://example.org/2022"]
+        );
+    }
+    #[test]
+    fn fetch_error_ends() {
+        let first = page(
+            r#"<link rel="next" href="https://example.org/2"/>"#,
+            "",
+            &["urn:1"],
+        );
+        let fetcher = |url: &str| Err(Error::InvalidFeed(url.to_string()));
+        let results: Vec<Result<Entry, Error>> = History::new(first, fetcher).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+        let complete = page(
+            r#"<link rel="next" href="https://example.org/2"/>"#,
+            "<fh:complete/>",
+            &["urn:1"],
+        );
+        let fetcher = |url: &str| Err(Error::InvalidFeed(url.to_string()));
+        assert_eq!(History::new(complete, fetcher).count(), 1);
+    }
+    fn new_entries(count: usize) -> Vec<Entry> {
+        let start = DateTime::from_str("2024-01-01T00:00:00Z").unwrap();
+        (0..count)
+            .rev()
+            .map(|i| Entry {
+                title: format!("Entry {}", i).into(),
+                guid: format!("urn:{}", i).into(),
+                updated: Some(start + Duration::days(i as i64)),
+                ..Default::default()
+            })
+            .collect()
+    }
+    fn new_config(style: PageStyle) -> PageConfig {
+        PageConfig {
+            page_size: 10,
+            style,
+            current_url: "https://example.org/feed".into(),
+            page_url: "https://example.org/feed/{}".into(),
+        }
+    }
+    #[test]
+    fn archive_pages() {
+        let template = Feed {
+            title: "Blog".to_string().into(),
+            id: Some("urn:blog".into()),
+            ..Default::default()
+        };
+        let pages = paginate(&template, &new_entries(25), &new_config(PageStyle::Archive));
+        let urls: Vec<&str> = pages.iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec!["https://example.org/feed", "https://example.org/feed/1"]
+        );
+        assert_eq!(pages[0].feed.entries.len(), 15);
+        assert_eq!(pages[1].feed.entries[0].guid.value, "urn:9");
+        assert_eq!(pages[0].feed.paging(), Paging::Subscription);
+        assert_eq!(pages[1].feed.paging(), Paging::Archived);
+        assert_eq!(
+            pages[1].feed.updated.unwrap().to_rfc3339(),
+            "2024-01-10T00:00:00+00:00"
+        );
+        // read the written documents back through History
+        for ftype in [FeedType::Atom, FeedType::Rss] {
+            let files = write_pages(&pages, ftype, &WriteConfig::default()).unwrap();
+            let mut documents: HashMap<String, Feed> = HashMap::new();
+            for file in files {
+                let feed = ModelFeed::read_from(&file.data[..], ftype).unwrap();
+                documents.insert(file.url, feed.into());
+            }
+            let current = documents["https://example.org/feed"].clone();
+            assert_eq!(
+                current.page_links().prev_archive.as_deref(),
+                Some("https://example.org/feed/1")
+            );
+            let fetcher = |url: &str| {
+                documents
+                    .get(url)
+                    .cloned()
+                    .ok_or_else(|| Error::InvalidFeed(url.to_string()))
+            };
+            let ids: Vec<String> = History::new(current, fetcher)
+                .map(|e| e.unwrap().guid.value)
+                .collect();
+            let expected: Vec<String> = (0..25).rev().map(|i| format!("urn:{}", i)).collect();
+            assert_eq!(ids, expected);
+        }
+    }
+    #[test]
+    fn archives_are_stable() {
+        let config = new_config(PageStyle::Archive);
+        let pages1 = paginate(&Feed::default(), &new_entries(25), &config);
+        let pages2 = paginate(&Feed::default(), &new_entries(31), &config);
+        assert_eq!(pages2.len(), 3);
+        // only the next-archive link is added to the old archive
+        assert_eq!(pages1[1].url, pages2[2].url);
+        assert_eq!(pages1[1].feed.entries, pages2[2].feed.entries);
+        assert_eq!(pages1[1].feed.updated, pages2[2].feed.updated);
+        assert_eq!(
+            pages2[2].feed.page_links().next_archive.as_deref(),
+            Some("https://example.org/feed/2")
+        );
+        let pages = paginate(&Feed::default(), &new_entries(5), &config);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].feed.paging(), Paging::Complete);
+    }
+    #[test]
+    fn paged_pages() {
+        let pages = paginate(
+            &Feed::default(),
+            &new_entries(25),
+            &new_config(PageStyle::Paged),
+        );
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[2].feed.entries.len(), 5);
+        let links = pages[1].feed.page_links();
+        assert_eq!(links.first.as_deref(), Some("https://example.org/feed"));
+        assert_eq!(links.previous.as_deref(), Some("https://example.org/feed"));
+        assert_eq!(links.next.as_deref(), Some("https://example.org/feed/3"));
+        assert_eq!(links.last.as_deref(), Some("https://example.org/feed/3"));
+        assert_eq!(pages[1].feed.paging(), Paging::Paged);
+    }
+}
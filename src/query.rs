@@ -0,0 +1,632 @@
+use crate::abstractions::{entry::Entry, fingerprint::normalize_text};
+use crate::date::parse_date;
+use crate::error::Error;
+use chrono::{DateTime, FixedOffset, Utc};
+use std::{fmt, ops, str::FromStr};
+use url::Url;
+
+// Entry filters, written as a query string, e.g.
+//   category:rust AND published>2024-01-01 AND NOT title~"sponsored"
+// Terms are field, operator and value, a bare value searches
+// title, summary and content. Terms next to each other are
+// combined with AND, parentheses group them.
+// AND and OR hold every operand in one node, so that long
+// queries do not nest as deep as they are long.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Term { field: Field, op: Op, value: Value },
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    // name or label of any category
+    Category,
+    // name or email of any author
+    Author,
+    Published,
+    Updated,
+    // host of any link, subdomains included
+    Domain,
+    // MIME type of the enclosures, "audio" or "audio/*"
+    // match every audio type
+    Type,
+    Title,
+    Summary,
+    Content,
+    // title, summary or content
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    // ':' case-insensitive equality, the same day for dates
+    Is,
+    // '~' case-insensitive substring
+    Contains,
+    Before,
+    After,
+    AtOrBefore,
+    AtOrAfter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Date(DateTime<FixedOffset>),
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self, Error> {
+        let mut parser = Parser {
+            input: query,
+            pos: 0,
+            depth: 0,
+        };
+        let parsed = parser.parse_or()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(parsed),
+            Some(_) => Err(parser.error("unexpected input")),
+        }
+    }
+
+    // Build a term, the value is parsed as a date for the
+    // date fields
+    pub fn term(field: Field, op: Op, value: &str) -> Result<Self, Error> {
+        let is_date = matches!(field, Field::Published | Field::Updated);
+        let value = match (is_date, op) {
+            (true, Op::Contains) => {
+                return Err(Error::InvalidQuery {
+                    message: "'~' does not apply to dates".into(),
+                    offset: 0,
+                })
+            }
+            (true, _) => Value::Date(parse_date(value).ok_or_else(|| Error::InvalidQuery {
+                message: format!("invalid date '{}'", value),
+                offset: 0,
+            })?),
+            (false, Op::Is | Op::Contains) => Value::Text(value.to_string()),
+            (false, _) => {
+                return Err(Error::InvalidQuery {
+                    message: "comparisons only apply to dates".into(),
+                    offset: 0,
+                })
+            }
+        };
+        Ok(Query::Term { field, op, value })
+    }
+
+    pub fn and(self, other: Query) -> Self {
+        let mut queries = match self {
+            Query::And(queries) => queries,
+            query => vec![query],
+        };
+        match other {
+            Query::And(others) => queries.extend(others),
+            query => queries.push(query),
+        }
+        Query::And(queries)
+    }
+
+    pub fn or(self, other: Query) -> Self {
+        let mut queries = match self {
+            Query::Or(queries) => queries,
+            query => vec![query],
+        };
+        match other {
+            Query::Or(others) => queries.extend(others),
+            query => queries.push(query),
+        }
+        Query::Or(queries)
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match self {
+            Query::Term { field, op, value } => term_matches(entry, *field, *op, value),
+            Query::And(queries) => queries.iter().all(|q| q.matches(entry)),
+            Query::Or(queries) => queries.iter().any(|q| q.matches(entry)),
+            Query::Not(a) => !a.matches(entry),
+        }
+    }
+
+    pub fn filter<'a>(&'a self, entries: &'a [Entry]) -> impl Iterator<Item = &'a Entry> + 'a {
+        entries.iter().filter(move |e| self.matches(e))
+    }
+}
+
+impl ops::Not for Query {
+    type Output = Query;
+
+    fn not(self) -> Self::Output {
+        Query::Not(Box::new(self))
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Query::Term { field, op, value } => {
+                let op = match op {
+                    Op::Is => ":",
+                    Op::Contains => "~",
+                    Op::Before => "<",
+                    Op::After => ">",
+                    Op::AtOrBefore => "<=",
+                    Op::AtOrAfter => ">=",
+                };
+                let value = match value {
+                    Value::Text(text) => text.replace('\\', "\\\\").replace('"', "\\\""),
+                    Value::Date(date) => date.to_rfc3339(),
+                };
+                write!(f, "{}{}\"{}\"", field.name(), op, value)
+            }
+            Query::And(queries) => write_list(f, queries, " AND "),
+            Query::Or(queries) => write_list(f, queries, " OR "),
+            Query::Not(a) => write!(f, "NOT {}", a),
+        }
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, queries: &[Query], separator: &str) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, query) in queries.iter().enumerate() {
+        if i > 0 {
+            write!(f, "{}", separator)?;
+        }
+        write!(f, "{}", query)?;
+    }
+    write!(f, ")")
+}
+
+impl Field {
+    const ALL: [Field; 10] = [
+        Field::Category,
+        Field::Author,
+        Field::Published,
+        Field::Updated,
+        Field::Domain,
+        Field::Type,
+        Field::Title,
+        Field::Summary,
+        Field::Content,
+        Field::Text,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Category => "category",
+            Field::Author => "author",
+            Field::Published => "published",
+            Field::Updated => "updated",
+            Field::Domain => "domain",
+            Field::Type => "type",
+            Field::Title => "title",
+            Field::Summary => "summary",
+            Field::Content => "content",
+            Field::Text => "text",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Field> {
+        Field::ALL
+            .into_iter()
+            .find(|f| f.name().eq_ignore_ascii_case(name))
+    }
+}
+
+fn term_matches(entry: &Entry, field: Field, op: Op, value: &Value) -> bool {
+    let text = match value {
+        Value::Text(text) => text,
+        Value::Date(date) => {
+            let entry_date = match field {
+                Field::Published => entry.published,
+                Field::Updated => entry.updated_date(),
+                _ => None,
+            };
+            return entry_date.is_some_and(|d| date_matches(d, op, *date));
+        }
+    };
+    let text = text.to_lowercase();
+    let compare = |candidate: &str| match op {
+        Op::Contains => candidate.to_lowercase().contains(&text),
+        _ => candidate.to_lowercase() == text,
+    };
+    match field {
+        Field::Category => entry
+            .categories
+            .iter()
+            .any(|c| compare(&c.name) || c.label.as_deref().is_some_and(compare)),
+        Field::Author => entry
+            .authors
+            .iter()
+            .any(|p| compare(&p.name) || p.email.as_deref().is_some_and(compare)),
+        Field::Domain => entry
+            .links
+            .iter()
+            .filter_map(|l| Url::parse(&l.href).ok())
+            .filter_map(|u| u.host_str().map(|h| h.to_lowercase()))
+            .any(|host| match op {
+                Op::Contains => host.contains(&text),
+                _ => host == text || host.ends_with(&format!(".{}", text)),
+            }),
+        Field::Type => {
+            let link_types = entry
+                .links
+                .iter()
+                .filter(|l| l.rel == "enclosure")
+                .filter_map(|l| l.mime_type.as_deref());
+            let enclosure_type = entry.enclosure.as_ref().map(|e| e.mime_type.as_str());
+            let major = text.trim_end_matches("/*");
+            enclosure_type.into_iter().chain(link_types).any(|t| {
+                let t = t.to_lowercase();
+                match op {
+                    Op::Contains => t.contains(&text),
+                    _ if !text.contains('/') || text.ends_with("/*") => {
+                        t.split('/').next() == Some(major)
+                    }
+                    _ => t == text,
+                }
+            })
+        }
+        Field::Title | Field::Summary | Field::Content | Field::Text => {
+            let title = [Field::Title, Field::Text]
+                .contains(&field)
+                .then_some(entry.title.value.as_str());
+            let summary = [Field::Summary, Field::Text]
+                .contains(&field)
+                .then(|| entry.summary.as_ref().map(|s| s.value.as_str()))
+                .flatten();
+            let content = [Field::Content, Field::Text]
+                .contains(&field)
+                .then(|| entry.content.as_ref().and_then(|c| c.value.as_deref()))
+                .flatten();
+            let text = normalize_text(&text);
+            [title, summary, content]
+                .into_iter()
+                .flatten()
+                .map(normalize_text)
+                .any(|candidate| match op {
+                    Op::Contains => candidate.contains(&text),
+                    _ => candidate == text,
+                })
+        }
+        Field::Published | Field::Updated => false,
+    }
+}
+
+fn date_matches(date: DateTime<FixedOffset>, op: Op, value: DateTime<FixedOffset>) -> bool {
+    match op {
+        Op::Is => date.with_timezone(&Utc).date_naive() == value.with_timezone(&Utc).date_naive(),
+        Op::Contains => false,
+        Op::Before => date < value,
+        Op::After => date > value,
+        Op::AtOrBefore => date <= value,
+        Op::AtOrAfter => date >= value,
+    }
+}
+
+// Nesting of NOT and parentheses past which a query is
+// rejected rather than risking the stack
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn parse_or(&mut self) -> Result<Query, Error> {
+        let mut query = self.parse_and()?;
+        while self.keyword("OR") {
+            query = query.or(self.parse_and()?);
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, Error> {
+        let mut query = self.parse_unary()?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some(')') => break,
+                _ if self.at_keyword("OR") => break,
+                _ => {}
+            }
+            self.keyword("AND");
+            query = query.and(self.parse_unary()?);
+        }
+        Ok(query)
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, Error> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.keyword("NOT") || self.eat('-') {
+            self.enter(start)?;
+            let query = !self.parse_unary()?;
+            self.depth -= 1;
+            return Ok(query);
+        }
+        if self.eat('(') {
+            self.enter(start)?;
+            let query = self.parse_or()?;
+            self.skip_whitespace();
+            if !self.eat(')') {
+                return Err(self.error("expected ')'"));
+            }
+            self.depth -= 1;
+            return Ok(query);
+        }
+        self.parse_term()
+    }
+
+    fn enter(&mut self, start: usize) -> Result<(), Error> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            self.pos = start;
+            return Err(self.error("query nested too deeply"));
+        }
+        Ok(())
+    }
+
+    fn parse_term(&mut self) -> Result<Query, Error> {
+        let start = self.pos;
+        let name: String = self
+            .rest()
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        self.pos += name.len();
+        let op = if self.eat(':') {
+            Some(Op::Is)
+        } else if self.eat('~') {
+            Some(Op::Contains)
+        } else if self.eat('<') {
+            Some(if self.eat('=') {
+                Op::AtOrBefore
+            } else {
+                Op::Before
+            })
+        } else if self.eat('>') {
+            Some(if self.eat('=') {
+                Op::AtOrAfter
+            } else {
+                Op::After
+            })
+        } else {
+            None
+        };
+        let (field, op) = match op {
+            Some(op) if !name.is_empty() => match Field::from_name(&name) {
+                Some(field) => (field, op),
+                None => {
+                    self.pos = start;
+                    return Err(self.error(&format!("unknown field '{}'", name)));
+                }
+            },
+            _ => {
+                // a bare value
+                self.pos = start;
+                (Field::Text, Op::Contains)
+            }
+        };
+        let value_start = self.pos;
+        let value = self.parse_value()?;
+        Query::term(field, op, &value).map_err(|err| match err {
+            Error::InvalidQuery { message, .. } => Error::InvalidQuery {
+                message,
+                offset: value_start,
+            },
+            other => other,
+        })
+    }
+
+    fn parse_value(&mut self) -> Result<String, Error> {
+        if self.eat('"') {
+            let mut value = String::new();
+            let mut chars = self.rest().char_indices();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        self.pos += i + 1;
+                        return Ok(value);
+                    }
+                    '\\' => match chars.next() {
+                        Some((_, c)) => value.push(c),
+                        None => break,
+                    },
+                    c => value.push(c),
+                }
+            }
+            self.pos = self.input.len();
+            return Err(self.error("unterminated quote"));
+        }
+        let value: String = self
+            .rest()
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '(' && *c != ')')
+            .collect();
+        if value.is_empty() {
+            return Err(self.error("expected a value"));
+        }
+        self.pos += value.len();
+        Ok(value)
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    // Operators are upper case words followed by a space or
+    // a parenthesis, "ORANGE" and "or" are values
+    fn at_keyword(&self, keyword: &str) -> bool {
+        let rest = self.rest();
+        rest.starts_with(keyword)
+            && rest[keyword.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_whitespace() || c == '(' || c == '"')
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        if self.at_keyword(keyword) {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::InvalidQuery {
+            message: message.to_string(),
+            offset: self.pos,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstractions::{category::Category, enclosure::Enclosure, person::Person};
+    use std::str::FromStr;
+
+    fn new_entries() -> Vec<Entry> {
+        vec![
+            Entry {
+                title: "Rust 2024 released".to_string().into(),
+                published: Some(DateTime::from_str("2024-02-01T10:00:00Z").unwrap()),
+                categories: vec![Category {
+                    name: "Rust".into(),
+                    ..Default::default()
+                }],
+                authors: vec![Person::parse("jane@example.org (Jane)")],
+                links: vec!["https://blog.rust-lang.org/2024".to_string().into()],
+                ..Default::default()
+            },
+            Entry {
+                title: "Sponsored: buy crabs".to_string().into(),
+                published: Some(DateTime::from_str("2024-03-01T10:00:00Z").unwrap()),
+                categories: vec![Category {
+                    name: "rust".into(),
+                    ..Default::default()
+                }],
+                enclosure: Some(Enclosure {
+                    url: "https://example.org/ad.mp3".into(),
+                    length: "1".into(),
+                    mime_type: "audio/mpeg".into(),
+                }),
+                ..Default::default()
+            },
+            Entry {
+                title: "Old news".to_string().into(),
+                published: Some(DateTime::from_str("2023-01-01T10:00:00Z").unwrap()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn titles(query: &str) -> Vec<String> {
+        let entries = new_entries();
+        let query = Query::parse(query).unwrap();
+        query
+            .filter(&entries)
+            .map(|e| e.title.value.clone())
+            .collect()
+    }
+
+    #[test]
+    fn example_query() {
+        assert_eq!(
+            titles(r#"category:rust AND published>2024-01-01 AND NOT title~"sponsored""#),
+            vec!["Rust 2024 released"]
+        );
+    }
+    #[test]
+    fn fields() {
+        assert_eq!(
+            titles("author:jane@example.org"),
+            vec!["Rust 2024 released"]
+        );
+        assert_eq!(titles("domain:rust-lang.org"), vec!["Rust 2024 released"]);
+        assert_eq!(titles("type:audio"), vec!["Sponsored: buy crabs"]);
+        assert_eq!(titles("published:2023-01-01"), vec!["Old news"]);
+        assert_eq!(titles("news"), vec!["Old news"]);
+    }
+    #[test]
+    fn precedence() {
+        assert_eq!(
+            titles("title~old OR category:rust published<2024-02-15"),
+            vec!["Rust 2024 released", "Old news"]
+        );
+        assert_eq!(
+            titles("(title~old OR category:rust) -type:audio/*"),
+            vec!["Rust 2024 released", "Old news"]
+        );
+    }
+    #[test]
+    fn display_round_trip() {
+        let query =
+            Query::parse(r#"category:rust NOT (title~"a \"b\"" OR updated>=2024-01-01)"#).unwrap();
+        assert_eq!(Query::from_str(&query.to_string()).unwrap(), query);
+    }
+    #[test]
+    fn long_queries() {
+        // neither matching nor dropping recurses by term count
+        let query = Query::parse(&"a ".repeat(200_000)).unwrap();
+        assert!(!query.matches(&Entry::default()));
+        let query = Query::parse(&vec!["a"; 200_000].join(" OR ")).unwrap();
+        assert!(!query.matches(&Entry::default()));
+        assert!(matches!(query, Query::Or(queries) if queries.len() == 200_000));
+    }
+    #[test]
+    fn invalid_queries() {
+        for (query, offset) in [
+            ("color:red", 0),
+            ("published>yesterday", 10),
+            ("published>2024-01-01T10:00+é1", 10),
+            ("title>a", 6),
+            ("(title:a", 8),
+            ("title:\"a", 8),
+            (&"-".repeat(20_000), MAX_DEPTH),
+            (&"(".repeat(20_000), MAX_DEPTH),
+            (&"NOT ".repeat(100), 4 * MAX_DEPTH),
+        ] {
+            match Query::parse(query) {
+                Err(Error::InvalidQuery { offset: o, .. }) => assert_eq!(o, offset, "{}", query),
+                other => panic!("{}: {:?}", query, other),
+            }
+        }
+    }
+}
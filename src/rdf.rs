@@ -0,0 +1,473 @@
+use crate::abstractions::person::Person;
+use crate::date::parse_date;
+use crate::detect::RDF_NAMESPACE;
+use crate::error::Error;
+use crate::models::WriteConfig;
+use crate::xml::{parse, write_extension, write_text_element, Element};
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, Event},
+    Writer,
+};
+use rss::{
+    extension::dublincore::{DublinCoreExtension, NAMESPACE as DC_NAMESPACE},
+    extension::syndication::{SyndicationExtension, NAMESPACE as SY_NAMESPACE},
+    extension::ExtensionMap as RssExtensionMap,
+    Channel as RssFeed, Guid as RssGuid, Image as RssImage, Item as RssEntry,
+    TextInput as RssTextInput,
+};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+
+// See https://web.resource.org/rss/1.0/spec
+pub const RSS1_NAMESPACE: &str = "http://purl.org/rss/1.0/";
+pub const RSS090_NAMESPACE: &str = "http://my.netscape.com/rdf/simple/0.9/";
+pub const CONTENT_NAMESPACE: &str = "http://purl.org/rss/1.0/modules/content/";
+
+// RSS 1.0 elements live either in the RSS 1.0 or in the
+// older RSS 0.90 namespace
+fn is_rss(element: &Element, local_name: &str) -> bool {
+    element.is(RSS1_NAMESPACE, local_name) || element.is(RSS090_NAMESPACE, local_name)
+}
+
+fn child_value(element: &Element, local_name: &str) -> Option<String> {
+    element
+        .children
+        .iter()
+        .find(|c| is_rss(c, local_name))
+        .and_then(|c| c.value())
+}
+
+fn dc_fields(dc: &DublinCoreExtension) -> [(&'static str, &Vec<String>); 15] {
+    [
+        ("title", &dc.titles),
+        ("creator", &dc.creators),
+        ("subject", &dc.subjects),
+        ("description", &dc.descriptions),
+        ("publisher", &dc.publishers),
+        ("contributor", &dc.contributors),
+        ("date", &dc.dates),
+        ("type", &dc.types),
+        ("format", &dc.formats),
+        ("identifier", &dc.identifiers),
+        ("source", &dc.sources),
+        ("language", &dc.languages),
+        ("relation", &dc.relations),
+        ("coverage", &dc.coverages),
+        ("rights", &dc.rights),
+    ]
+}
+
+fn push_dc(dc: &mut DublinCoreExtension, local_name: &str, value: String) {
+    let field = match local_name {
+        "title" => &mut dc.titles,
+        "creator" => &mut dc.creators,
+        "subject" => &mut dc.subjects,
+        "description" => &mut dc.descriptions,
+        "publisher" => &mut dc.publishers,
+        "contributor" => &mut dc.contributors,
+        "date" => &mut dc.dates,
+        "type" => &mut dc.types,
+        "format" => &mut dc.formats,
+        "identifier" => &mut dc.identifiers,
+        "source" => &mut dc.sources,
+        "language" => &mut dc.languages,
+        "relation" => &mut dc.relations,
+        "coverage" => &mut dc.coverages,
+        "rights" => &mut dc.rights,
+        _ => return,
+    };
+    field.push(value);
+}
+
+// Sort the module elements of a channel or an item: Dublin Core,
+// syndication and any other namespaced element as extension
+struct Modules {
+    dublin_core: Option<DublinCoreExtension>,
+    syndication: Option<SyndicationExtension>,
+    extensions: RssExtensionMap,
+}
+
+fn read_modules(element: &Element) -> Modules {
+    let mut dc = DublinCoreExtension::default();
+    let mut sy: Option<SyndicationExtension> = None;
+    let mut extensions: RssExtensionMap = BTreeMap::new();
+    for child in element.children.iter() {
+        match child.namespace.as_deref() {
+            Some(DC_NAMESPACE) => {
+                if let Some(value) = child.value() {
+                    push_dc(&mut dc, child.local_name(), value);
+                }
+            }
+            Some(SY_NAMESPACE) => {
+                let sy = sy.get_or_insert_with(SyndicationExtension::default);
+                let value = child.value().unwrap_or_default();
+                match child.local_name() {
+                    "updatePeriod" => {
+                        if let Ok(period) = value.parse() {
+                            sy.period = period;
+                        }
+                    }
+                    "updateFrequency" => {
+                        if let Ok(frequency) = value.parse() {
+                            sy.frequency = frequency;
+                        }
+                    }
+                    "updateBase" => sy.base = value,
+                    _ => {}
+                }
+            }
+            Some(RSS1_NAMESPACE)
+            | Some(RSS090_NAMESPACE)
+            | Some(RDF_NAMESPACE)
+            | Some(CONTENT_NAMESPACE) => {}
+            _ => {
+                if let Some(prefix) = child.prefix() {
+                    extensions
+                        .entry(prefix.to_string())
+                        .or_default()
+                        .entry(child.local_name().to_string())
+                        .or_default()
+                        .push(child.into());
+                }
+            }
+        }
+    }
+    Modules {
+        dublin_core: (dc != DublinCoreExtension::default()).then_some(dc),
+        syndication: sy,
+        extensions,
+    }
+}
+
+fn read_item(element: &Element) -> RssEntry {
+    let link = child_value(element, "link");
+    let guid: Option<RssGuid> = element.attr("about").map(|about| RssGuid {
+        value: about.clone(),
+        permalink: Some(about) == link.as_ref(),
+    });
+    let content: Option<String> = element
+        .children
+        .iter()
+        .find(|c| c.is(CONTENT_NAMESPACE, "encoded"))
+        .and_then(|c| c.value());
+    let modules = read_modules(element);
+    RssEntry {
+        title: child_value(element, "title"),
+        link,
+        description: child_value(element, "description"),
+        guid,
+        content,
+        extensions: modules.extensions,
+        dublin_core_ext: modules.dublin_core,
+        ..Default::default()
+    }
+}
+
+pub fn read_from<R: BufRead>(reader: R) -> Result<RssFeed, Error> {
+    let mut reader = reader;
+    let mut data: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let root = parse(&data)?;
+    if !root.is(RDF_NAMESPACE, "RDF") {
+        return Err(Error::InvalidFeed(format!(
+            "expected rdf:RDF root element, found {}",
+            root.name
+        )));
+    }
+    let channel = root
+        .children
+        .iter()
+        .find(|c| is_rss(c, "channel"))
+        .ok_or_else(|| Error::InvalidFeed("missing channel element".into()))?;
+    let modules = read_modules(channel);
+    let mut feed = RssFeed {
+        title: child_value(channel, "title").unwrap_or_default(),
+        link: child_value(channel, "link").unwrap_or_default(),
+        description: child_value(channel, "description").unwrap_or_default(),
+        extensions: modules.extensions,
+        dublin_core_ext: modules.dublin_core,
+        syndication_ext: modules.syndication,
+        namespaces: root.namespaces(),
+        ..Default::default()
+    };
+    // image, items and textinput are siblings of the channel
+    for child in root.children.iter() {
+        if is_rss(child, "item") {
+            feed.items.push(read_item(child));
+        } else if is_rss(child, "image") {
+            feed.image = Some(RssImage {
+                url: child_value(child, "url").unwrap_or_default(),
+                title: child_value(child, "title").unwrap_or_default(),
+                link: child_value(child, "link").unwrap_or_default(),
+                ..Default::default()
+            });
+        } else if is_rss(child, "textinput") {
+            feed.text_input = Some(RssTextInput {
+                title: child_value(child, "title").unwrap_or_default(),
+                description: child_value(child, "description").unwrap_or_default(),
+                name: child_value(child, "name").unwrap_or_default(),
+                link: child_value(child, "link").unwrap_or_default(),
+            });
+        }
+    }
+    Ok(feed)
+}
+
+fn write_modules<W: Write>(
+    writer: &mut Writer<W>,
+    dc: &DublinCoreExtension,
+    extensions: &RssExtensionMap,
+) -> Result<(), Error> {
+    for (name, values) in dc_fields(dc) {
+        for value in values {
+            write_text_element(writer, &format!("dc:{}", name), value)?;
+        }
+    }
+    for extension in extensions.values().flat_map(|m| m.values()).flatten() {
+        write_extension(writer, extension)?;
+    }
+    Ok(())
+}
+
+fn item_about(item: &RssEntry) -> String {
+    match (&item.guid, &item.link) {
+        (Some(guid), _) => guid.value.clone(),
+        (None, Some(link)) => link.clone(),
+        (None, None) => "".into(),
+    }
+}
+
+// RSS 2.0 fields without RSS 1.0 equivalent are written
+// with the Dublin Core module
+fn item_dublin_core(item: &RssEntry) -> DublinCoreExtension {
+    let mut dc = DublinCoreExtension::default();
+    if let Some(author) = item.author.as_deref() {
+        dc.creators
+            .extend(Person::parse_list(author).into_iter().map(String::from));
+    }
+    if let Some(date) = item.pub_date.as_deref() {
+        dc.dates.push(match parse_date(date) {
+            Some(d) => d.to_rfc3339(),
+            None => date.to_string(),
+        });
+    }
+    dc.subjects
+        .extend(item.categories.iter().map(|c| c.name.clone()));
+    if let Some(ext) = item.dublin_core_ext.as_ref() {
+        for (name, values) in dc_fields(ext) {
+            for value in values {
+                push_dc(&mut dc, name, value.clone());
+            }
+        }
+    }
+    dc
+}
+
+fn channel_dublin_core(channel: &RssFeed) -> DublinCoreExtension {
+    let mut dc = DublinCoreExtension::default();
+    if let Some(editor) = channel.managing_editor.as_deref() {
+        dc.creators
+            .extend(Person::parse_list(editor).into_iter().map(String::from));
+    }
+    if let Some(date) = channel
+        .last_build_date
+        .as_ref()
+        .or(channel.pub_date.as_ref())
+    {
+        dc.dates.push(match parse_date(date) {
+            Some(d) => d.to_rfc3339(),
+            None => date.to_string(),
+        });
+    }
+    dc.languages.extend(channel.language.clone());
+    dc.rights.extend(channel.copyright.clone());
+    dc.subjects
+        .extend(channel.categories.iter().map(|c| c.name.clone()));
+    if let Some(ext) = channel.dublin_core_ext.as_ref() {
+        for (name, values) in dc_fields(ext) {
+            for value in values {
+                push_dc(&mut dc, name, value.clone());
+            }
+        }
+    }
+    dc
+}
+
+pub fn write_to<W: Write>(channel: &RssFeed, writer: W) -> Result<W, Error> {
+    write_with_config(channel, writer, &WriteConfig::default())
+}
+
+pub fn write_with_config<W: Write>(
+    channel: &RssFeed,
+    writer: W,
+    config: &WriteConfig,
+) -> Result<W, Error> {
+    let mut writer = match config.indent_size {
+        Some(size) => Writer::new_with_indent(writer, config.indent_char, size),
+        None => Writer::new(writer),
+    };
+    if config.write_document_declaration {
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    }
+    let mut namespaces: BTreeMap<String, String> = channel.namespaces.clone();
+    namespaces.insert("rdf".into(), RDF_NAMESPACE.into());
+    namespaces.insert("dc".into(), DC_NAMESPACE.into());
+    namespaces.insert("sy".into(), SY_NAMESPACE.into());
+    namespaces.insert("content".into(), CONTENT_NAMESPACE.into());
+    let mut root = BytesStart::new("rdf:RDF");
+    root.push_attribute(("xmlns", RSS1_NAMESPACE));
+    for (prefix, namespace) in namespaces.iter() {
+        root.push_attribute((format!("xmlns:{}", prefix).as_str(), namespace.as_str()));
+    }
+    writer.write_event(Event::Start(root))?;
+
+    let mut start = BytesStart::new("channel");
+    start.push_attribute(("rdf:about", channel.link.as_str()));
+    writer.write_event(Event::Start(start))?;
+    write_text_element(&mut writer, "title", &channel.title)?;
+    write_text_element(&mut writer, "link", &channel.link)?;
+    write_text_element(&mut writer, "description", &channel.description)?;
+    if let Some(image) = channel.image.as_ref() {
+        let mut start = BytesStart::new("image");
+        start.push_attribute(("rdf:resource", image.url.as_str()));
+        writer.write_event(Event::Empty(start))?;
+    }
+    writer.write_event(Event::Start(BytesStart::new("items")))?;
+    writer.write_event(Event::Start(BytesStart::new("rdf:Seq")))?;
+    for item in channel.items.iter() {
+        let mut li = BytesStart::new("rdf:li");
+        li.push_attribute(("rdf:resource", item_about(item).as_str()));
+        writer.write_event(Event::Empty(li))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("rdf:Seq")))?;
+    writer.write_event(Event::End(BytesEnd::new("items")))?;
+    if let Some(text_input) = channel.text_input.as_ref() {
+        let mut start = BytesStart::new("textinput");
+        start.push_attribute(("rdf:resource", text_input.link.as_str()));
+        writer.write_event(Event::Empty(start))?;
+    }
+    if let Some(sy) = channel.syndication_ext.as_ref() {
+        write_text_element(&mut writer, "sy:updatePeriod", &sy.period.to_string())?;
+        write_text_element(&mut writer, "sy:updateFrequency", &sy.frequency.to_string())?;
+        write_text_element(&mut writer, "sy:updateBase", &sy.base)?;
+    }
+    write_modules(
+        &mut writer,
+        &channel_dublin_core(channel),
+        &channel.extensions,
+    )?;
+    writer.write_event(Event::End(BytesEnd::new("channel")))?;
+
+    if let Some(image) = channel.image.as_ref() {
+        let mut start = BytesStart::new("image");
+        start.push_attribute(("rdf:about", image.url.as_str()));
+        writer.write_event(Event::Start(start))?;
+        write_text_element(&mut writer, "title", &image.title)?;
+        write_text_element(&mut writer, "url", &image.url)?;
+        write_text_element(&mut writer, "link", &image.link)?;
+        writer.write_event(Event::End(BytesEnd::new("image")))?;
+    }
+    for item in channel.items.iter() {
+        let mut start = BytesStart::new("item");
+        start.push_attribute(("rdf:about", item_about(item).as_str()));
+        writer.write_event(Event::Start(start))?;
+        let fields = [
+            ("title", &item.title),
+            ("link", &item.link),
+            ("description", &item.description),
+            ("content:encoded", &item.content),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                write_text_element(&mut writer, name, value)?;
+            }
+        }
+        write_modules(&mut writer, &item_dublin_core(item), &item.extensions)?;
+        writer.write_event(Event::End(BytesEnd::new("item")))?;
+    }
+    if let Some(text_input) = channel.text_input.as_ref() {
+        let mut start = BytesStart::new("textinput");
+        start.push_attribute(("rdf:about", text_input.link.as_str()));
+        writer.write_event(Event::Start(start))?;
+        write_text_element(&mut writer, "title", &text_input.title)?;
+        write_text_element(&mut writer, "description", &text_input.description)?;
+        write_text_element(&mut writer, "name", &text_input.name)?;
+        write_text_element(&mut writer, "link", &text_input.link)?;
+        writer.write_event(Event::End(BytesEnd::new("textinput")))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("rdf:RDF")))?;
+    Ok(writer.into_inner())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const FEED: &str = r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+    xmlns="http://purl.org/rss/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+  <channel rdf:about="http://example.org/">
+    <title>Example</title>
+    <link>http://example.org/</link>
+    <description>An example</description>
+    <dc:language>en</dc:language>
+    <sy:updatePeriod>daily</sy:updatePeriod>
+    <sy:updateFrequency>2</sy:updateFrequency>
+    <image rdf:resource="http://example.org/logo.png" />
+    <items>
+      <rdf:Seq>
+        <rdf:li rdf:resource="http://example.org/1" />
+      </rdf:Seq>
+    </items>
+  </channel>
+  <image rdf:about="http://example.org/logo.png">
+    <title>Example</title>
+    <url>http://example.org/logo.png</url>
+    <link>http://example.org/</link>
+  </image>
+  <item rdf:about="http://example.org/1">
+    <title>First</title>
+    <link>http://example.org/1</link>
+    <dc:creator>John</dc:creator>
+    <dc:date>2024-01-01T10:00:00+01:00</dc:date>
+    <dc:subject>rust</dc:subject>
+  </item>
+</rdf:RDF>"#;
+
+    #[test]
+    fn read_rdf() {
+        let feed = read_from(FEED.as_bytes()).unwrap();
+        assert_eq!(feed.title, "Example");
+        assert_eq!(
+            feed.image.as_ref().unwrap().url,
+            "http://example.org/logo.png"
+        );
+        assert_eq!(feed.syndication_ext.as_ref().unwrap().frequency, 2);
+        assert_eq!(
+            feed.dublin_core_ext.as_ref().unwrap().languages,
+            vec!["en".to_string()]
+        );
+        assert_eq!(feed.items.len(), 1);
+        let item = &feed.items[0];
+        assert_eq!(item.guid.as_ref().unwrap().value, "http://example.org/1");
+        assert!(item.guid.as_ref().unwrap().permalink);
+        let dc = item.dublin_core_ext.as_ref().unwrap();
+        assert_eq!(dc.creators, vec!["John".to_string()]);
+        assert_eq!(dc.subjects, vec!["rust".to_string()]);
+    }
+    #[test]
+    fn read_not_rdf() {
+        assert!(read_from(&b"<rss><channel></channel></rss>"[..]).is_err());
+    }
+    #[test]
+    fn rdf_no_loss() {
+        let feed1 = read_from(FEED.as_bytes()).unwrap();
+        let buf = write_to(&feed1, Vec::new()).unwrap();
+        let mut feed2 = read_from(&buf[..]).unwrap();
+        // the writer declares the namespaces it uses
+        feed2.namespaces = feed1.namespaces.clone();
+        assert_eq!(feed1, feed2);
+    }
+}
@@ -0,0 +1,560 @@
+use crate::abstractions::{entry::Entry, feed::Feed};
+use crate::date::parse_date;
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc, Weekday};
+use rss::extension::syndication::UpdatePeriod;
+use std::collections::{HashMap, HashSet};
+
+// Longest wait a feed or a server can ask for, larger values
+// are capped instead of trusted
+const MAX_HINT_SECONDS: i64 = 7 * 24 * 3600;
+
+const SYNDICATION_NAMESPACE: &str = "http://purl.org/rss/1.0/modules/syndication/";
+
+#[derive(Debug, Clone)]
+pub struct ScheduleConfig {
+    // Bounds of the interval guessed from the entries, the
+    // hints of the feed and the server may exceed the maximum
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+    // Interval of feeds without hints or enough entries
+    pub default_interval: Duration,
+    // Longest wait after consecutive failures
+    pub max_backoff: Duration,
+    // Feeds polled at the same time
+    pub max_concurrent: usize,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::minutes(15),
+            max_interval: Duration::days(1),
+            default_interval: Duration::hours(1),
+            max_backoff: Duration::days(2),
+            max_concurrent: 4,
+        }
+    }
+}
+
+// What a feed says about its own updates
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedHints {
+    // RSS ttl
+    pub ttl: Option<Duration>,
+    // sy:updatePeriod divided by sy:updateFrequency
+    pub update_period: Option<Duration>,
+    // RSS skipHours and skipDays, in UTC
+    pub skip_hours: Vec<u32>,
+    pub skip_days: Vec<Weekday>,
+    // Median time between the recent entries
+    pub observed: Option<Duration>,
+}
+
+impl FeedHints {
+    pub fn from_feed(feed: &Feed) -> Self {
+        let ttl = feed
+            .ttl
+            .as_deref()
+            .and_then(|t| t.trim().parse::<i64>().ok())
+            .filter(|t| *t > 0)
+            .map(|t| hint(t.saturating_mul(60)));
+        // Atom feeds keep the syndication module as extension
+        let prefix = feed
+            .namespaces
+            .iter()
+            .find(|(_, ns)| ns.as_str() == SYNDICATION_NAMESPACE)
+            .map(|(prefix, _)| prefix.as_str())
+            .unwrap_or("sy");
+        let sy = feed.extensions.get(prefix);
+        let sy_value = |name: &str| {
+            sy.and_then(|ext| ext.get(name))
+                .and_then(|values| values.first())
+                .and_then(|value| value.value.clone())
+        };
+        let (period, frequency) = match &feed.syndication_ext {
+            Some(ext) => (Some(ext.period.clone()), Some(ext.frequency)),
+            None => (
+                sy_value("updatePeriod").and_then(|p| p.trim().parse::<UpdatePeriod>().ok()),
+                sy_value("updateFrequency").and_then(|f| f.trim().parse::<u32>().ok()),
+            ),
+        };
+        let update_period = period.map(|period| {
+            let period = match period {
+                UpdatePeriod::Hourly => Duration::hours(1),
+                UpdatePeriod::Daily => Duration::days(1),
+                UpdatePeriod::Weekly => Duration::weeks(1),
+                UpdatePeriod::Monthly => Duration::days(30),
+                UpdatePeriod::Yearly => Duration::days(365),
+            };
+            let frequency = i32::try_from(frequency.unwrap_or(1).max(1)).unwrap_or(i32::MAX);
+            hint((period / frequency).num_seconds())
+        });
+        Self {
+            ttl,
+            update_period,
+            skip_hours: feed
+                .skip_hours
+                .iter()
+                .filter_map(|h| h.trim().parse::<u32>().ok())
+                // 24 is a common spelling of midnight
+                .map(|h| h % 24)
+                .collect(),
+            skip_days: feed
+                .skip_days
+                .iter()
+                .filter_map(|d| d.trim().parse::<Weekday>().ok())
+                .collect(),
+            observed: observed_interval(&feed.entries),
+        }
+    }
+
+    // The feed asks not to be polled more often than this
+    fn declared(&self) -> Option<Duration> {
+        self.ttl.max(self.update_period)
+    }
+
+    fn skipped(&self, date: DateTime<Utc>) -> bool {
+        self.skip_hours.contains(&date.hour()) || self.skip_days.contains(&date.weekday())
+    }
+}
+
+// Caching headers of the last response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpHints {
+    pub cache_control: Option<String>,
+    pub expires: Option<String>,
+    pub retry_after: Option<String>,
+}
+
+#[cfg(feature = "fetch")]
+impl From<&crate::fetch::FetchResponse> for HttpHints {
+    fn from(value: &crate::fetch::FetchResponse) -> Self {
+        Self {
+            cache_control: value.cache_control.clone(),
+            expires: value.expires.clone(),
+            retry_after: value.retry_after.clone(),
+        }
+    }
+}
+
+impl HttpHints {
+    // The response may be reused until then
+    fn fresh_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if let Some(cache_control) = &self.cache_control {
+            let mut max_age = None;
+            for directive in cache_control.split(',').map(str::trim) {
+                let (name, value) = directive.split_once('=').unwrap_or((directive, ""));
+                match name.to_ascii_lowercase().as_str() {
+                    "no-cache" | "no-store" => return None,
+                    "max-age" => max_age = value.trim_matches('"').parse::<i64>().ok(),
+                    _ => {}
+                }
+            }
+            // max-age takes precedence over Expires
+            if let Some(max_age) = max_age {
+                return Some(add(now, hint(max_age)));
+            }
+        }
+        self.expires
+            .as_deref()
+            .and_then(parse_date)
+            .map(|d| d.with_timezone(&Utc).min(add(now, hint(MAX_HINT_SECONDS))))
+    }
+
+    fn retry_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let value = self.retry_after.as_deref()?.trim();
+        match value.parse::<i64>() {
+            Ok(seconds) => Some(add(now, hint(seconds))),
+            Err(_) => parse_date(value)
+                .map(|d| d.with_timezone(&Utc).min(add(now, hint(MAX_HINT_SECONDS)))),
+        }
+    }
+}
+
+fn hint(seconds: i64) -> Duration {
+    Duration::try_seconds(seconds.clamp(0, MAX_HINT_SECONDS)).unwrap_or_default()
+}
+
+// Intervals set in the config are not capped, the result is
+fn add(date: DateTime<Utc>, interval: Duration) -> DateTime<Utc> {
+    date.checked_add_signed(interval)
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+// Median gap between the last entries, newest first or not
+fn observed_interval(entries: &[Entry]) -> Option<Duration> {
+    let mut dates: Vec<DateTime<Utc>> = entries
+        .iter()
+        .filter_map(Entry::date)
+        .map(|d| d.with_timezone(&Utc))
+        .collect();
+    dates.sort();
+    dates.dedup();
+    let dates = &dates[dates.len().saturating_sub(11)..];
+    let mut gaps: Vec<Duration> = dates.windows(2).map(|w| w[1] - w[0]).collect();
+    if gaps.len() < 2 {
+        return None;
+    }
+    gaps.sort();
+    Some(gaps[gaps.len() / 2])
+}
+
+// Time of the next poll after a successful one
+pub fn next_poll(
+    hints: &FeedHints,
+    http: &HttpHints,
+    now: DateTime<Utc>,
+    config: &ScheduleConfig,
+) -> DateTime<Utc> {
+    let interval = hints
+        .observed
+        .unwrap_or(config.default_interval)
+        .clamp(config.min_interval, config.max_interval);
+    // the feed and the server can only slow polling down
+    let interval = interval.max(hints.declared().unwrap_or(interval));
+    let mut next = add(now, interval);
+    if let Some(fresh) = http.fresh_until(now) {
+        next = next.max(fresh);
+    }
+    if let Some(retry) = http.retry_after(now) {
+        next = next.max(retry);
+    }
+    skip_forward(hints, next)
+}
+
+// Time of the next poll after consecutive failures, the
+// interval doubles from the minimum up to max_backoff
+pub fn next_retry(
+    failures: u32,
+    http: &HttpHints,
+    now: DateTime<Utc>,
+    config: &ScheduleConfig,
+) -> DateTime<Utc> {
+    let factor = 1i32 << failures.saturating_sub(1).min(20);
+    let backoff = config
+        .min_interval
+        .checked_mul(factor)
+        .unwrap_or(config.max_backoff)
+        .min(config.max_backoff);
+    let next = add(now, backoff);
+    match http.retry_after(now) {
+        Some(retry) => next.max(retry),
+        None => next,
+    }
+}
+
+// Move a time out of the skipped hours and days
+fn skip_forward(hints: &FeedHints, date: DateTime<Utc>) -> DateTime<Utc> {
+    let mut next = date;
+    // a feed skipping every hour is not skipped at all
+    for _ in 0..24 * 7 {
+        if !hints.skipped(next) {
+            return next;
+        }
+        let hour = next.duration_trunc(Duration::hours(1)).unwrap_or(next);
+        match hour.checked_add_signed(Duration::hours(1)) {
+            Some(date) => next = date,
+            None => break,
+        }
+    }
+    date
+}
+
+#[derive(Debug, Clone)]
+struct FeedState {
+    next_poll: DateTime<Utc>,
+    failures: u32,
+    hints: FeedHints,
+}
+
+// Poll times of a set of feeds, by URL
+#[derive(Debug, Clone, Default)]
+pub struct Scheduler {
+    config: ScheduleConfig,
+    feeds: HashMap<String, FeedState>,
+    running: HashSet<String>,
+}
+
+impl Scheduler {
+    pub fn new(config: ScheduleConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    // A new feed is due at once
+    pub fn add(&mut self, url: &str, now: DateTime<Utc>) {
+        self.feeds.entry(url.into()).or_insert(FeedState {
+            next_poll: now,
+            failures: 0,
+            hints: FeedHints::default(),
+        });
+    }
+
+    pub fn remove(&mut self, url: &str) {
+        self.feeds.remove(url);
+        self.running.remove(url);
+    }
+
+    pub fn next_poll_of(&self, url: &str) -> Option<DateTime<Utc>> {
+        self.feeds.get(url).map(|s| s.next_poll)
+    }
+
+    // Feeds to poll now, the most overdue first, without
+    // exceeding max_concurrent polls in progress. Each must be
+    // reported back with one of the completion methods.
+    pub fn due(&mut self, now: DateTime<Utc>) -> Vec<String> {
+        let mut due: Vec<(&String, &FeedState)> = self
+            .feeds
+            .iter()
+            .filter(|(url, state)| state.next_poll <= now && !self.running.contains(*url))
+            .collect();
+        due.sort_by_key(|(url, state)| (state.next_poll, *url));
+        let free = self
+            .config
+            .max_concurrent
+            .saturating_sub(self.running.len());
+        let due: Vec<String> = due
+            .into_iter()
+            .take(free)
+            .map(|(url, _)| url.clone())
+            .collect();
+        self.running.extend(due.iter().cloned());
+        due
+    }
+
+    // When the next feed becomes due, None while every feed
+    // is being polled
+    pub fn next_wakeup(&self) -> Option<DateTime<Utc>> {
+        self.feeds
+            .iter()
+            .filter(|(url, _)| !self.running.contains(*url))
+            .map(|(_, state)| state.next_poll)
+            .min()
+    }
+
+    pub fn complete(&mut self, url: &str, feed: &Feed, http: &HttpHints, now: DateTime<Utc>) {
+        let hints = FeedHints::from_feed(feed);
+        self.update(url, |state, config| {
+            state.hints = hints;
+            state.failures = 0;
+            state.next_poll = next_poll(&state.hints, http, now, config);
+        });
+    }
+
+    // 304 Not Modified, the hints of the last feed still apply
+    pub fn complete_not_modified(&mut self, url: &str, http: &HttpHints, now: DateTime<Utc>) {
+        self.update(url, |state, config| {
+            state.failures = 0;
+            state.next_poll = next_poll(&state.hints, http, now, config);
+        });
+    }
+
+    pub fn complete_error(&mut self, url: &str, http: &HttpHints, now: DateTime<Utc>) {
+        self.update(url, |state, config| {
+            state.failures = state.failures.saturating_add(1);
+            state.next_poll = next_retry(state.failures, http, now, config);
+        });
+    }
+
+    fn update<F: FnOnce(&mut FeedState, &ScheduleConfig)>(&mut self, url: &str, update: F) {
+        self.running.remove(url);
+        if let Some(state) = self.feeds.get_mut(url) {
+            update(state, &self.config);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Feed as ModelFeed, FeedType};
+    use std::str::FromStr;
+
+    fn date(value: &str) -> DateTime<Utc> {
+        DateTime::from_str(value).unwrap()
+    }
+
+    fn read(data: &str, ftype: FeedType) -> Feed {
+        ModelFeed::read_from(data.as_bytes(), ftype).unwrap().into()
+    }
+
+    const RSS: &str = r#"<rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+<channel><title>Blog</title><link>https://example.org</link><description>Blog</description>
+<ttl>120</ttl>
+<sy:updatePeriod>daily</sy:updatePeriod><sy:updateFrequency>4</sy:updateFrequency>
+<skipHours><hour>0</hour><hour>1</hour></skipHours>
+<skipDays><day>Sunday</day></skipDays>
+</channel></rss>"#;
+
+    #[test]
+    fn rss_hints() {
+        let hints = FeedHints::from_feed(&read(RSS, FeedType::Rss));
+        assert_eq!(hints.ttl, Some(Duration::hours(2)));
+        assert_eq!(hints.update_period, Some(Duration::hours(6)));
+        assert_eq!(hints.skip_hours, vec![0, 1]);
+        assert_eq!(hints.skip_days, vec![Weekday::Sun]);
+        let config = ScheduleConfig::default();
+        // Friday 12:00, the update period wins over the ttl
+        let now = date("2024-01-05T12:00:00Z");
+        assert_eq!(
+            next_poll(&hints, &HttpHints::default(), now, &config),
+            date("2024-01-05T18:00:00Z")
+        );
+        // Saturday 20:00 plus 6 hours falls on a Sunday
+        let now = date("2024-01-06T20:00:00Z");
+        assert_eq!(
+            next_poll(&hints, &HttpHints::default(), now, &config),
+            date("2024-01-08T02:00:00Z")
+        );
+    }
+    #[test]
+    fn atom_hints_and_observed() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"
+    xmlns:sy="http://purl.org/rss/1.0/modules/syndication/"><title>Blog</title>
+<sy:updatePeriod>hourly</sy:updatePeriod>
+<entry><id>urn:1</id><title>1</title><updated>2024-01-01T00:00:00Z</updated></entry>
+<entry><id>urn:2</id><title>2</title><updated>2024-01-01T03:00:00Z</updated></entry>
+<entry><id>urn:3</id><title>3</title><updated>2024-01-01T05:00:00Z</updated></entry>
+<entry><id>urn:4</id><title>4</title><updated>2024-01-01T09:00:00Z</updated></entry>
+</feed>"#;
+        let hints = FeedHints::from_feed(&read(atom, FeedType::Atom));
+        assert_eq!(hints.update_period, Some(Duration::hours(1)));
+        assert_eq!(hints.observed, Some(Duration::hours(3)));
+        let now = date("2024-01-01T10:00:00Z");
+        let config = ScheduleConfig::default();
+        assert_eq!(
+            next_poll(&hints, &HttpHints::default(), now, &config),
+            date("2024-01-01T13:00:00Z")
+        );
+    }
+    #[test]
+    fn atom_hints_other_prefix() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"
+    xmlns:syn="http://purl.org/rss/1.0/modules/syndication/"><title>Blog</title>
+<syn:updatePeriod>daily</syn:updatePeriod><syn:updateFrequency>2</syn:updateFrequency>
+</feed>"#;
+        let hints = FeedHints::from_feed(&read(atom, FeedType::Atom));
+        assert_eq!(hints.update_period, Some(Duration::hours(12)));
+    }
+    #[test]
+    fn http_hints() {
+        let hints = FeedHints::default();
+        let config = ScheduleConfig::default();
+        let now = date("2024-01-01T10:00:00Z");
+        let http = HttpHints {
+            cache_control: Some("public, max-age=7200".into()),
+            expires: Some("Mon, 01 Jan 2024 20:00:00 GMT".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            next_poll(&hints, &http, now, &config),
+            date("2024-01-01T12:00:00Z")
+        );
+        let http = HttpHints {
+            expires: Some("Mon, 01 Jan 2024 20:00:00 GMT".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            next_poll(&hints, &http, now, &config),
+            date("2024-01-01T20:00:00Z")
+        );
+        let http = HttpHints {
+            cache_control: Some("no-cache".into()),
+            retry_after: Some("Mon, 01 Jan 2024 10:30:00 GMT".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            next_poll(&hints, &http, now, &config),
+            date("2024-01-01T11:00:00Z")
+        );
+    }
+    #[test]
+    fn oversized_hints() {
+        let config = ScheduleConfig::default();
+        let now = date("2024-01-01T00:00:00Z");
+        let cap = now + Duration::weeks(1);
+        for ttl in ["9999999999999999", "999999999999"] {
+            let feed = Feed {
+                ttl: Some(ttl.into()),
+                ..Default::default()
+            };
+            let hints = FeedHints::from_feed(&feed);
+            assert_eq!(hints.ttl, Some(Duration::weeks(1)));
+            assert_eq!(next_poll(&hints, &HttpHints::default(), now, &config), cap);
+        }
+        let feed = read(&RSS.replace(">4<", ">4294967295<"), FeedType::Rss);
+        let hints = FeedHints::from_feed(&feed);
+        // divided by i32::MAX rather than a wrapped negative number
+        assert_eq!(hints.update_period, Some(Duration::zero()));
+        let http = HttpHints {
+            cache_control: Some("max-age=99999999999999999".into()),
+            ..Default::default()
+        };
+        assert_eq!(next_poll(&FeedHints::default(), &http, now, &config), cap);
+        let http = HttpHints {
+            retry_after: Some("9223372036854775".into()),
+            ..Default::default()
+        };
+        assert_eq!(next_poll(&FeedHints::default(), &http, now, &config), cap);
+        assert_eq!(next_retry(1, &http, now, &config), cap);
+        let hints = FeedHints {
+            ttl: Some(Duration::MAX),
+            ..Default::default()
+        };
+        assert_eq!(
+            next_poll(&hints, &HttpHints::default(), now, &config),
+            DateTime::<Utc>::MAX_UTC
+        );
+    }
+    #[test]
+    fn backoff() {
+        let config = ScheduleConfig::default();
+        let now = date("2024-01-01T00:00:00Z");
+        let none = HttpHints::default();
+        assert_eq!(
+            next_retry(1, &none, now, &config),
+            now + Duration::minutes(15)
+        );
+        assert_eq!(next_retry(3, &none, now, &config), now + Duration::hours(1));
+        assert_eq!(next_retry(40, &none, now, &config), now + Duration::days(2));
+        let http = HttpHints {
+            retry_after: Some("7200".into()),
+            ..Default::default()
+        };
+        assert_eq!(next_retry(1, &http, now, &config), now + Duration::hours(2));
+    }
+    #[test]
+    fn concurrency_limit() {
+        let mut scheduler = Scheduler::new(ScheduleConfig {
+            max_concurrent: 2,
+            ..Default::default()
+        });
+        let now = date("2024-01-01T00:00:00Z");
+        for url in ["https://a", "https://b", "https://c"] {
+            scheduler.add(url, now);
+        }
+        assert_eq!(scheduler.due(now), vec!["https://a", "https://b"]);
+        assert!(scheduler.due(now).is_empty());
+        assert_eq!(scheduler.next_wakeup(), Some(now));
+        scheduler.complete(
+            "https://a",
+            &read(RSS, FeedType::Rss),
+            &HttpHints::default(),
+            now,
+        );
+        assert_eq!(scheduler.due(now), vec!["https://c"]);
+        scheduler.complete_error("https://b", &HttpHints::default(), now);
+        scheduler.complete_not_modified("https://c", &HttpHints::default(), now);
+        assert_eq!(
+            scheduler.next_poll_of("https://a"),
+            Some(date("2024-01-01T06:00:00Z"))
+        );
+        assert_eq!(scheduler.next_wakeup(), Some(now + Duration::minutes(15)));
+        assert_eq!(
+            scheduler.due(now + Duration::minutes(15)),
+            vec!["https://b"]
+        );
+    }
+}
@@ -0,0 +1,456 @@
+use crate::abstractions::{
+    entry::Entry,
+    feed::Feed,
+    fingerprint::{Fingerprint, FingerprintStrategy},
+};
+use crate::error::Error;
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::path::Path;
+
+// Schema changes, applied in order. The index of the last one
+// applied plus one is kept as user_version, so a migration
+// must never be edited once released, only new ones added.
+const MIGRATIONS: [&str; 1] = [r#"
+CREATE TABLE feeds (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE,
+    data TEXT NOT NULL
+);
+CREATE TABLE entries (
+    id INTEGER PRIMARY KEY,
+    feed_id INTEGER NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+    fingerprint TEXT NOT NULL,
+    date TEXT,
+    data TEXT NOT NULL,
+    UNIQUE (feed_id, fingerprint)
+);
+CREATE INDEX entries_date ON entries (date);
+CREATE TABLE entry_categories (
+    entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    PRIMARY KEY (entry_id, name)
+);
+CREATE INDEX entry_categories_name ON entry_categories (name);
+"#];
+
+// Outcome of an upsert, by entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpsertStats {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+// Entries to select, every field left to None matches all.
+// Dates compare with the published date, or else the updated
+// one, like aggregate sorts entries.
+#[derive(Debug, Clone, Default)]
+pub struct EntryQuery {
+    pub feed: Option<String>,
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+    // Compared without case
+    pub category: Option<String>,
+    pub limit: Option<usize>,
+}
+
+// Feeds by URL and their entries by fingerprint. Entries are
+// kept whole, with their extensions, as JSON.
+pub struct Store {
+    connection: Connection,
+    fingerprint: FingerprintStrategy,
+}
+
+impl Store {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Store::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Store::from_connection(Connection::open_in_memory()?)
+    }
+
+    // The schema is migrated to the latest version
+    pub fn from_connection(connection: Connection) -> Result<Self, Error> {
+        let mut connection = connection;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut connection)?;
+        Ok(Self {
+            connection,
+            fingerprint: FingerprintStrategy::default(),
+        })
+    }
+
+    // Identity of the entries, changing it on a store that
+    // already holds entries duplicates them
+    pub fn with_fingerprint(mut self, strategy: FingerprintStrategy) -> Self {
+        self.fingerprint = strategy;
+        self
+    }
+
+    pub fn schema_version(&self) -> Result<u32, Error> {
+        Ok(self
+            .connection
+            .pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    // Store the metadata and the entries of a feed
+    pub fn upsert_feed(&mut self, url: &str, feed: &Feed) -> Result<UpsertStats, Error> {
+        let metadata = Feed {
+            entries: Vec::new(),
+            ..feed.clone()
+        };
+        let data = serde_json::to_string(&metadata)?;
+        let tx = self.connection.transaction()?;
+        tx.execute(
+            "INSERT INTO feeds (url, data) VALUES (?1, ?2)
+             ON CONFLICT (url) DO UPDATE SET data = excluded.data",
+            params![url, data],
+        )?;
+        let stats = upsert_entries(&tx, url, &feed.entries, self.fingerprint)?;
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    // Store entries without touching the metadata of their
+    // feed, which is created empty when unknown
+    pub fn upsert_entries(&mut self, url: &str, entries: &[Entry]) -> Result<UpsertStats, Error> {
+        let data = serde_json::to_string(&Feed::default())?;
+        let tx = self.connection.transaction()?;
+        tx.execute(
+            "INSERT INTO feeds (url, data) VALUES (?1, ?2) ON CONFLICT (url) DO NOTHING",
+            params![url, data],
+        )?;
+        let stats = upsert_entries(&tx, url, entries, self.fingerprint)?;
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    // The metadata of a feed, without entries
+    pub fn feed(&self, url: &str) -> Result<Option<Feed>, Error> {
+        let data: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT data FROM feeds WHERE url = ?1",
+                params![url],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(data.map(|d| serde_json::from_str(&d)).transpose()?)
+    }
+
+    pub fn feeds(&self) -> Result<Vec<String>, Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT url FROM feeds ORDER BY url")?;
+        let urls = statement.query_map([], |row| row.get(0))?;
+        Ok(urls.collect::<Result<Vec<String>, _>>()?)
+    }
+
+    // Removes the feed and its entries
+    pub fn remove_feed(&mut self, url: &str) -> Result<bool, Error> {
+        let removed = self
+            .connection
+            .execute("DELETE FROM feeds WHERE url = ?1", params![url])?;
+        Ok(removed > 0)
+    }
+
+    pub fn entry(&self, url: &str, fingerprint: &Fingerprint) -> Result<Option<Entry>, Error> {
+        let data: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT e.data FROM entries e JOIN feeds f ON f.id = e.feed_id
+                 WHERE f.url = ?1 AND e.fingerprint = ?2",
+                params![url, fingerprint.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(data.map(|d| serde_json::from_str(&d)).transpose()?)
+    }
+
+    // Matching entries, newest first and undated ones last
+    pub fn entries(&self, query: &EntryQuery) -> Result<Vec<Entry>, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT e.data FROM entries e JOIN feeds f ON f.id = e.feed_id
+             WHERE (?1 IS NULL OR f.url = ?1)
+             AND (?2 IS NULL OR e.date >= ?2)
+             AND (?3 IS NULL OR e.date < ?3)
+             AND (?4 IS NULL OR EXISTS (SELECT 1 FROM entry_categories c
+                 WHERE c.entry_id = e.id AND c.name = ?4))
+             ORDER BY e.date IS NULL, e.date DESC, e.id
+             LIMIT ?5",
+        )?;
+        let limit = query
+            .limit
+            .map_or(-1, |l| i64::try_from(l).unwrap_or(i64::MAX));
+        let rows = statement.query_map(
+            params![
+                query.feed,
+                query.since.map(sortable_date),
+                query.until.map(sortable_date),
+                query.category.as_deref().map(str::to_lowercase),
+                limit
+            ],
+            |row| row.get::<_, String>(0),
+        )?;
+        let mut entries = Vec::new();
+        for data in rows {
+            entries.push(serde_json::from_str(&data?)?);
+        }
+        Ok(entries)
+    }
+}
+
+fn migrate(connection: &mut Connection) -> Result<(), Error> {
+    let version: u32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version as usize > MIGRATIONS.len() {
+        return Err(Error::UnsupportedSchema(version));
+    }
+    for (version, migration) in (1..).zip(MIGRATIONS).skip(version as usize) {
+        let tx = connection.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn upsert_entries(
+    tx: &Transaction,
+    url: &str,
+    entries: &[Entry],
+    strategy: FingerprintStrategy,
+) -> Result<UpsertStats, Error> {
+    let feed_id: i64 =
+        tx.query_row("SELECT id FROM feeds WHERE url = ?1", params![url], |row| {
+            row.get(0)
+        })?;
+    let mut stats = UpsertStats::default();
+    for entry in entries {
+        let fingerprint = entry.fingerprint_with(strategy).to_string();
+        let data = serde_json::to_string(entry)?;
+        let date = entry.date().map(sortable_date);
+        let existing: Option<(i64, String)> = tx
+            .query_row(
+                "SELECT id, data FROM entries WHERE feed_id = ?1 AND fingerprint = ?2",
+                params![feed_id, fingerprint],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let entry_id = match existing {
+            Some((_, old)) if old == data => {
+                stats.unchanged += 1;
+                continue;
+            }
+            Some((id, _)) => {
+                tx.execute(
+                    "UPDATE entries SET date = ?2, data = ?3 WHERE id = ?1",
+                    params![id, date, data],
+                )?;
+                tx.execute(
+                    "DELETE FROM entry_categories WHERE entry_id = ?1",
+                    params![id],
+                )?;
+                stats.updated += 1;
+                id
+            }
+            None => {
+                tx.execute(
+                    "INSERT INTO entries (feed_id, fingerprint, date, data)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![feed_id, fingerprint, date, data],
+                )?;
+                stats.inserted += 1;
+                tx.last_insert_rowid()
+            }
+        };
+        // lowercased here, COLLATE NOCASE only folds ASCII
+        for category in &entry.categories {
+            tx.execute(
+                "INSERT OR IGNORE INTO entry_categories (entry_id, name) VALUES (?1, ?2)",
+                params![entry_id, category.name.to_lowercase()],
+            )?;
+        }
+    }
+    Ok(stats)
+}
+
+// UTC with a fixed precision, so that dates sort as text
+fn sortable_date(date: DateTime<FixedOffset>) -> String {
+    date.with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Feed as ModelFeed, FeedType};
+    use std::str::FromStr;
+
+    const PODCAST: &str = r#"<rss version="2.0"
+    xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:media="http://search.yahoo.com/mrss/">
+<channel><title>Podcast</title><link>https://example.org</link><description>Show</description>
+<item><title>Episode 2</title><guid>urn:2</guid>
+<pubDate>Wed, 03 Jan 2024 10:00:00 +0100</pubDate>
+<category>Rust</category><category>Audio</category><category>Économie</category>
+<itunes:duration>00:42:00</itunes:duration><dc:creator>Jane</dc:creator>
+<dc:publisher>Example Radio</dc:publisher>
+<media:thumbnail url="https://example.org/2.jpg"/>
+<enclosure url="https://example.org/2.mp3" length="42" type="audio/mpeg"/></item>
+<item><title>Episode 1</title><guid>urn:1</guid>
+<pubDate>Mon, 01 Jan 2024 10:00:00 GMT</pubDate><category>rust</category></item>
+<item><title>Undated</title><guid>urn:0</guid></item>
+</channel></rss>"#;
+
+    fn podcast() -> Feed {
+        ModelFeed::read_from(PODCAST.as_bytes(), FeedType::Rss)
+            .unwrap()
+            .into()
+    }
+
+    fn date(value: &str) -> DateTime<FixedOffset> {
+        DateTime::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn full_entry_round_trip() {
+        let feed = podcast();
+        let mut store = Store::open_in_memory().unwrap();
+        assert_eq!(store.schema_version().unwrap() as usize, MIGRATIONS.len());
+        let stats = store
+            .upsert_feed("https://example.org/feed", &feed)
+            .unwrap();
+        assert_eq!(stats.inserted, 3);
+        let entry = store
+            .entry("https://example.org/feed", &feed.entries[0].fingerprint())
+            .unwrap()
+            .unwrap();
+        assert!(entry.itunes_ext.is_some());
+        assert!(entry.dublin_core_ext.is_some());
+        assert!(entry.extensions.contains_key("media"));
+        assert_eq!(entry, feed.entries[0]);
+        let metadata = store.feed("https://example.org/feed").unwrap().unwrap();
+        assert_eq!(metadata.title, feed.title);
+        assert!(metadata.entries.is_empty());
+    }
+    #[test]
+    fn upsert_by_identity() {
+        let mut feed = podcast();
+        let mut store = Store::open_in_memory().unwrap();
+        store
+            .upsert_feed("https://example.org/feed", &feed)
+            .unwrap();
+        feed.entries[1].title = "Episode 1, edited".to_string().into();
+        let stats = store
+            .upsert_feed("https://example.org/feed", &feed)
+            .unwrap();
+        assert_eq!(
+            stats,
+            UpsertStats {
+                inserted: 0,
+                updated: 1,
+                unchanged: 2,
+            }
+        );
+        let entries = store.entries(&EntryQuery::default()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].title.value, "Episode 1, edited");
+        // entries of another feed are kept apart
+        let stats = store
+            .upsert_entries("https://other.example.org/feed", &feed.entries[..1])
+            .unwrap();
+        assert_eq!(stats.inserted, 1);
+        assert_eq!(store.feeds().unwrap().len(), 2);
+        assert!(store.remove_feed("https://other.example.org/feed").unwrap());
+        assert_eq!(store.entries(&EntryQuery::default()).unwrap().len(), 3);
+    }
+    #[test]
+    fn query_by_feed_date_category() {
+        let mut store = Store::open_in_memory().unwrap();
+        store
+            .upsert_feed("https://example.org/feed", &podcast())
+            .unwrap();
+        let titles = |query: EntryQuery| -> Vec<String> {
+            store
+                .entries(&query)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.title.value)
+                .collect()
+        };
+        assert_eq!(
+            titles(EntryQuery::default()),
+            vec!["Episode 2", "Episode 1", "Undated"]
+        );
+        assert_eq!(
+            titles(EntryQuery {
+                category: Some("RUST".into()),
+                ..Default::default()
+            }),
+            vec!["Episode 2", "Episode 1"]
+        );
+        assert_eq!(
+            titles(EntryQuery {
+                category: Some("ÉCONOMIE".into()),
+                ..Default::default()
+            }),
+            vec!["Episode 2"]
+        );
+        // 10:00 +01:00 is 09:00 UTC
+        assert_eq!(
+            titles(EntryQuery {
+                since: Some(date("2024-01-03T09:30:00+00:00")),
+                ..Default::default()
+            }),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            titles(EntryQuery {
+                since: Some(date("2024-01-02T00:00:00+00:00")),
+                until: Some(date("2024-01-04T00:00:00+00:00")),
+                ..Default::default()
+            }),
+            vec!["Episode 2"]
+        );
+        assert_eq!(
+            titles(EntryQuery {
+                feed: Some("https://other.example.org/feed".into()),
+                ..Default::default()
+            }),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            titles(EntryQuery {
+                limit: Some(1),
+                ..Default::default()
+            }),
+            vec!["Episode 2"]
+        );
+    }
+    #[test]
+    fn migrations() {
+        let path = std::env::temp_dir().join(format!("feed_store_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut store = Store::open(&path).unwrap();
+            store
+                .upsert_feed("https://example.org/feed", &podcast())
+                .unwrap();
+        }
+        // reopening keeps the data and does not migrate again
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap() as usize, MIGRATIONS.len());
+        assert_eq!(store.entries(&EntryQuery::default()).unwrap().len(), 3);
+        drop(store);
+        let connection = Connection::open(&path).unwrap();
+        connection.pragma_update(None, "user_version", 99).unwrap();
+        assert!(matches!(
+            Store::from_connection(connection),
+            Err(Error::UnsupportedSchema(99))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,470 @@
+use crate::abstractions::{entry::Entry, feed::Feed};
+use crate::error::Error;
+use crate::models::{Feed as ModelFeed, FeedType};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use std::collections::HashMap;
+use url::{form_urlencoded, Url};
+
+// WebSub, see https://www.w3.org/TR/websub/
+pub const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+// Hubs a feed is published to and the topic to subscribe
+// to, its self link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HubLinks {
+    pub hubs: Vec<String>,
+    pub topic: String,
+}
+
+impl Feed {
+    pub fn hub_links(&self) -> Option<HubLinks> {
+        let topic = self.links.iter().find(|l| l.rel == "self")?;
+        let topic = resolve(self.base.as_deref(), &topic.href);
+        // JSON Feed hubs of another type (rssCloud...) keep it
+        // as link title
+        let hubs: Vec<String> = self
+            .links
+            .iter()
+            .filter(|l| l.rel == "hub")
+            .filter(|l| {
+                l.title.as_deref().is_none_or(|t| {
+                    t.eq_ignore_ascii_case("WebSub") || t.eq_ignore_ascii_case("PubSubHubbub")
+                })
+            })
+            .map(|l| resolve(Some(&topic), &l.href))
+            .collect();
+        if hubs.is_empty() {
+            return None;
+        }
+        Some(HubLinks { hubs, topic })
+    }
+}
+
+fn resolve(base: Option<&str>, href: &str) -> String {
+    let base = base.and_then(|b| Url::parse(b).ok());
+    match base
+        .as_ref()
+        .map_or_else(|| Url::parse(href), |b| b.join(href))
+    {
+        Ok(url) => url.to_string(),
+        Err(_) => href.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Subscribe,
+    Unsubscribe,
+}
+
+impl Mode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Subscribe => "subscribe",
+            Mode::Unsubscribe => "unsubscribe",
+        }
+    }
+}
+
+// A request to POST to the hub with FORM_CONTENT_TYPE, the
+// hub accepts it with 202 Accepted then verifies the intent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HubRequest {
+    pub hub: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionState {
+    // waiting for the hub to verify the intent
+    Pending(Mode),
+    Active,
+    Denied,
+    Unsubscribed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub hub: String,
+    pub topic: String,
+    pub callback: String,
+    pub secret: Option<String>,
+    pub state: SubscriptionState,
+    // None until the hub confirms a lease
+    pub expires: Option<DateTime<Utc>>,
+    // Reason given by the hub for a denial
+    pub reason: Option<String>,
+}
+
+// Answer of the callback to a GET from the hub
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    // respond 200 with the challenge as body
+    Confirmed(String),
+    // a denial notification, respond 200
+    Denied,
+    // not an intent of ours, respond 404
+    Unknown,
+}
+
+// Subscriptions by topic of a callback endpoint
+#[derive(Debug, Clone, Default)]
+pub struct Subscriber {
+    subscriptions: HashMap<String, Subscription>,
+}
+
+impl Subscriber {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A secret of up to 200 bytes makes the hub sign the
+    // content it distributes
+    pub fn subscribe(
+        &mut self,
+        hub: &str,
+        topic: &str,
+        callback: &str,
+        secret: Option<&str>,
+        lease_seconds: Option<u64>,
+    ) -> HubRequest {
+        let subscription = Subscription {
+            hub: hub.into(),
+            topic: topic.into(),
+            callback: callback.into(),
+            secret: secret.map(String::from),
+            state: SubscriptionState::Pending(Mode::Subscribe),
+            expires: None,
+            reason: None,
+        };
+        let request = hub_request(&subscription, Mode::Subscribe, lease_seconds);
+        self.subscriptions.insert(topic.into(), subscription);
+        request
+    }
+
+    pub fn unsubscribe(&mut self, topic: &str) -> Option<HubRequest> {
+        let subscription = self.subscriptions.get_mut(topic)?;
+        subscription.state = SubscriptionState::Pending(Mode::Unsubscribe);
+        Some(hub_request(subscription, Mode::Unsubscribe, None))
+    }
+
+    pub fn subscription(&self, topic: &str) -> Option<&Subscription> {
+        self.subscriptions.get(topic)
+    }
+
+    pub fn subscriptions(&self) -> impl Iterator<Item = &Subscription> {
+        self.subscriptions.values()
+    }
+
+    // Handle the query string of a verification or denial
+    // request sent by the hub to the callback
+    pub fn verify_intent(&mut self, query: &str) -> Verification {
+        let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let get = |name: &str| params.get(name).map(String::as_str);
+        let Some(subscription) = get("hub.topic").and_then(|t| self.subscriptions.get_mut(t))
+        else {
+            return Verification::Unknown;
+        };
+        match (get("hub.mode"), subscription.state) {
+            (Some("denied"), SubscriptionState::Pending(Mode::Subscribe))
+            | (Some("denied"), SubscriptionState::Active) => {
+                subscription.state = SubscriptionState::Denied;
+                subscription.reason = get("hub.reason").map(String::from);
+                Verification::Denied
+            }
+            (Some(mode), SubscriptionState::Pending(pending)) if mode == pending.as_str() => {
+                let Some(challenge) = get("hub.challenge") else {
+                    return Verification::Unknown;
+                };
+                match pending {
+                    Mode::Subscribe => {
+                        subscription.state = SubscriptionState::Active;
+                        subscription.expires = get("hub.lease_seconds")
+                            .and_then(|l| l.parse::<i64>().ok())
+                            .and_then(Duration::try_seconds)
+                            .map(|lease| Utc::now() + lease);
+                    }
+                    Mode::Unsubscribe => {
+                        subscription.state = SubscriptionState::Unsubscribed;
+                        subscription.expires = None;
+                    }
+                }
+                Verification::Confirmed(challenge.into())
+            }
+            _ => Verification::Unknown,
+        }
+    }
+
+    // Entries of content distributed by the hub for a topic.
+    // When the subscription has a secret, the X-Hub-Signature
+    // header must match or the content is rejected.
+    pub fn receive(
+        &self,
+        topic: &str,
+        signature: Option<&str>,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<Entry>, Error> {
+        let subscription = self
+            .subscriptions
+            .get(topic)
+            .filter(|s| s.state == SubscriptionState::Active)
+            .ok_or_else(|| Error::RejectedPush(format!("no subscription to {}", topic)))?;
+        if let Some(secret) = &subscription.secret {
+            let signature =
+                signature.ok_or_else(|| Error::RejectedPush("missing X-Hub-Signature".into()))?;
+            if !verify_signature(secret.as_bytes(), signature, body) {
+                return Err(Error::RejectedPush(format!(
+                    "signature mismatch: {}",
+                    signature
+                )));
+            }
+        }
+        parse_push(body, content_type)
+    }
+}
+
+fn hub_request(subscription: &Subscription, mode: Mode, lease_seconds: Option<u64>) -> HubRequest {
+    let mut body = form_urlencoded::Serializer::new(String::new());
+    body.append_pair("hub.callback", &subscription.callback)
+        .append_pair("hub.mode", mode.as_str())
+        .append_pair("hub.topic", &subscription.topic);
+    if let Some(lease) = lease_seconds {
+        body.append_pair("hub.lease_seconds", &lease.to_string());
+    }
+    if let (Mode::Subscribe, Some(secret)) = (mode, &subscription.secret) {
+        body.append_pair("hub.secret", secret);
+    }
+    HubRequest {
+        hub: subscription.hub.clone(),
+        body: body.finish(),
+    }
+}
+
+// Check a X-Hub-Signature header, "method=hexdigest", against
+// the body; sha1, sha256, sha384 and sha512 are supported
+pub fn verify_signature(secret: &[u8], header: &str, body: &[u8]) -> bool {
+    let Some((method, digest)) = header.trim().split_once('=') else {
+        return false;
+    };
+    let Ok(digest) = hex::decode(digest) else {
+        return false;
+    };
+    fn check<M: Mac + hmac::digest::KeyInit>(secret: &[u8], body: &[u8], digest: &[u8]) -> bool {
+        let Ok(mut mac) = <M as hmac::digest::KeyInit>::new_from_slice(secret) else {
+            return false;
+        };
+        mac.update(body);
+        mac.verify_slice(digest).is_ok()
+    }
+    match method.to_ascii_lowercase().as_str() {
+        "sha1" => check::<Hmac<Sha1>>(secret, body, &digest),
+        "sha256" => check::<Hmac<Sha256>>(secret, body, &digest),
+        "sha384" => check::<Hmac<Sha384>>(secret, body, &digest),
+        "sha512" => check::<Hmac<Sha512>>(secret, body, &digest),
+        _ => false,
+    }
+}
+
+// X-Hub-Signature value of a body, as a hub computes it
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// Entries of a pushed document, a full or partial feed in
+// any supported format
+pub fn parse_push(body: &[u8], content_type: Option<&str>) -> Result<Vec<Entry>, Error> {
+    let (feed, _) = ModelFeed::read_with_encoding(body, FeedType::Auto, content_type)?;
+    let mut feed: Feed = feed.into();
+    feed.inherit_authors();
+    Ok(feed.entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Blog</title>
+<id>urn:blog</id><updated>2024-01-02T00:00:00Z</updated>
+<link rel="self" href="https://example.org/feed.xml"/>
+<link rel="hub" href="/hub"/>
+<author><name>Jane</name></author>
+<entry><title>New</title><id>urn:1</id><updated>2024-01-02T00:00:00Z</updated></entry>
+</feed>"#;
+
+    // Stand-in for a hub, it answers requests the way a hub
+    // would and calls back the subscriber in process
+    #[derive(Default)]
+    struct Hub {
+        subscribers: HashMap<String, (String, Option<String>)>,
+    }
+
+    impl Hub {
+        fn post(&mut self, request: &HubRequest, subscriber: &mut Subscriber) -> u16 {
+            let params: HashMap<String, String> = form_urlencoded::parse(request.body.as_bytes())
+                .into_owned()
+                .collect();
+            let topic = &params["hub.topic"];
+            let mode = &params["hub.mode"];
+            let query = form_urlencoded::Serializer::new(String::new())
+                .append_pair("hub.mode", mode)
+                .append_pair("hub.topic", topic)
+                .append_pair("hub.challenge", "c-123")
+                .append_pair("hub.lease_seconds", "3600")
+                .finish();
+            if subscriber.verify_intent(&query) != Verification::Confirmed("c-123".into()) {
+                return 202;
+            }
+            match mode.as_str() {
+                "subscribe" => {
+                    let secret = params.get("hub.secret").cloned();
+                    self.subscribers
+                        .insert(topic.clone(), (params["hub.callback"].clone(), secret));
+                }
+                _ => {
+                    self.subscribers.remove(topic);
+                }
+            }
+            202
+        }
+
+        fn publish(&self, topic: &str, body: &[u8]) -> Option<Option<String>> {
+            let (_, secret) = self.subscribers.get(topic)?;
+            Some(secret.as_ref().map(|s| sign(s.as_bytes(), body)))
+        }
+    }
+
+    #[test]
+    fn discover_hub() {
+        let feed: Feed = ModelFeed::read_from(FEED.as_bytes(), FeedType::Atom)
+            .unwrap()
+            .into();
+        let links = feed.hub_links().unwrap();
+        assert_eq!(links.topic, "https://example.org/feed.xml");
+        assert_eq!(links.hubs, vec!["https://example.org/hub"]);
+    }
+    #[test]
+    fn discover_hub_relative() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom" xml:base="https://example.org/blog/">
+<title>Blog</title><id>urn:blog</id><updated>2024-01-01T00:00:00Z</updated>
+<link rel="self" href="feed.xml"/><link rel="hub" href="/hub"/></feed>"#;
+        let feed: Feed = ModelFeed::read_from(atom.as_bytes(), FeedType::Atom)
+            .unwrap()
+            .into();
+        let links = feed.hub_links().unwrap();
+        assert_eq!(links.topic, "https://example.org/blog/feed.xml");
+        assert_eq!(links.hubs, vec!["https://example.org/hub"]);
+    }
+    #[test]
+    fn discover_hub_rss() {
+        let rss = r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom"><channel>
+<title>Blog</title><link>https://example.org</link><description>Blog</description>
+<atom:link rel="hub" href="https://hub.example.org/"/>
+<atom:link rel="self" href="https://example.org/rss.xml"/>
+</channel></rss>"#;
+        let feed: Feed = ModelFeed::read_from(rss.as_bytes(), FeedType::Rss)
+            .unwrap()
+            .into();
+        let links = feed.hub_links().unwrap();
+        assert_eq!(links.topic, "https://example.org/rss.xml");
+        assert_eq!(links.hubs, vec!["https://hub.example.org/"]);
+    }
+    #[test]
+    fn subscribe_and_receive() {
+        let mut hub = Hub::default();
+        let mut subscriber = Subscriber::new();
+        let topic = "https://example.org/feed.xml";
+        let request = subscriber.subscribe(
+            "https://example.org/hub",
+            topic,
+            "https://reader.example.net/push/1",
+            Some("s3cret"),
+            Some(3600),
+        );
+        assert!(request.body.contains("hub.mode=subscribe"));
+        assert!(request
+            .body
+            .contains("hub.callback=https%3A%2F%2Freader.example.net%2Fpush%2F1"));
+        assert_eq!(hub.post(&request, &mut subscriber), 202);
+        let subscription = subscriber.subscription(topic).unwrap();
+        assert_eq!(subscription.state, SubscriptionState::Active);
+        assert!(subscription.expires.is_some());
+
+        let signature = hub.publish(topic, FEED.as_bytes()).unwrap();
+        let entries = subscriber
+            .receive(
+                topic,
+                signature.as_deref(),
+                Some("application/atom+xml"),
+                FEED.as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].authors[0].name, "Jane");
+        // tampered content
+        let tampered = FEED.replace("New", "Forged");
+        assert!(matches!(
+            subscriber.receive(topic, signature.as_deref(), None, tampered.as_bytes()),
+            Err(Error::RejectedPush(_))
+        ));
+        assert!(subscriber
+            .receive(topic, None, None, FEED.as_bytes())
+            .is_err());
+
+        let request = subscriber.unsubscribe(topic).unwrap();
+        hub.post(&request, &mut subscriber);
+        assert_eq!(
+            subscriber.subscription(topic).unwrap().state,
+            SubscriptionState::Unsubscribed
+        );
+        assert!(hub.publish(topic, FEED.as_bytes()).is_none());
+    }
+    #[test]
+    fn unknown_and_denied_intents() {
+        let mut subscriber = Subscriber::new();
+        subscriber.subscribe("https://hub", "https://topic", "https://cb", None, None);
+        assert_eq!(
+            subscriber.verify_intent("hub.mode=subscribe&hub.topic=https://other&hub.challenge=x"),
+            Verification::Unknown
+        );
+        assert_eq!(
+            subscriber
+                .verify_intent("hub.mode=unsubscribe&hub.topic=https://topic&hub.challenge=x"),
+            Verification::Unknown
+        );
+        assert_eq!(
+            subscriber.verify_intent("hub.mode=denied&hub.topic=https://topic&hub.reason=spam"),
+            Verification::Denied
+        );
+        let subscription = subscriber.subscription("https://topic").unwrap();
+        assert_eq!(subscription.state, SubscriptionState::Denied);
+        assert_eq!(subscription.reason.as_deref(), Some("spam"));
+    }
+    #[test]
+    fn signature_methods() {
+        // RFC 2202 and RFC 4231 test cases
+        assert!(verify_signature(
+            b"Jefe",
+            "sha1=effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            b"what do ya want for nothing?"
+        ));
+        assert!(verify_signature(
+            b"Jefe",
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            b"what do ya want for nothing?"
+        ));
+        assert!(!verify_signature(b"Jefe", "md5=00", b""));
+        assert!(!verify_signature(b"Jefe", "sha1=zz", b""));
+        let signature = sign(b"key", b"body");
+        assert!(verify_signature(b"key", &signature, b"body"));
+        assert!(!verify_signature(b"other", &signature, b"body"));
+    }
+}
@@ -0,0 +1,270 @@
+use crate::error::{Error, Position};
+use quick_xml::Reader;
+use quick_xml::{
+    escape::resolve_predefined_entity,
+    events::{BytesEnd, BytesStart, BytesText, Event},
+    name::ResolveResult,
+    NsReader, Writer, XmlVersion,
+};
+use rss::extension::Extension as RssExtension;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+// Minimal owned XML tree, used for the formats that are
+// not handled by the rss and atom_syndication crates
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Element {
+    // qualified name, as written in the document
+    pub name: String,
+    pub namespace: Option<String>,
+    pub attrs: BTreeMap<String, String>,
+    pub text: String,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn prefix(&self) -> Option<&str> {
+        self.name.split_once(':').map(|(prefix, _)| prefix)
+    }
+
+    pub fn local_name(&self) -> &str {
+        match self.name.split_once(':') {
+            Some((_, local)) => local,
+            None => &self.name,
+        }
+    }
+
+    pub fn is(&self, namespace: &str, local_name: &str) -> bool {
+        self.namespace.as_deref() == Some(namespace) && self.local_name() == local_name
+    }
+
+    // Attribute lookup ignoring the prefix, e.g. "about"
+    // matches "rdf:about"
+    pub fn attr(&self, local_name: &str) -> Option<&String> {
+        self.attrs.iter().find_map(|(key, value)| {
+            let local = key.split_once(':').map(|(_, l)| l).unwrap_or(key);
+            (local == local_name).then_some(value)
+        })
+    }
+
+    pub fn value(&self) -> Option<String> {
+        let text = self.text.trim();
+        if text.is_empty() {
+            None
+        } else {
+            Some(text.to_string())
+        }
+    }
+
+    // Namespaces declared on this element, by prefix
+    pub fn namespaces(&self) -> BTreeMap<String, String> {
+        self.attrs
+            .iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix("xmlns:")
+                    .map(|prefix| (prefix.to_string(), value.clone()))
+            })
+            .collect()
+    }
+}
+
+impl From<&Element> for RssExtension {
+    fn from(value: &Element) -> Self {
+        let mut children: BTreeMap<String, Vec<RssExtension>> = BTreeMap::new();
+        for child in value.children.iter() {
+            children
+                .entry(child.local_name().to_string())
+                .or_default()
+                .push(child.into());
+        }
+        Self {
+            name: value.name.clone(),
+            value: value.value(),
+            attrs: value.attrs.clone(),
+            children,
+        }
+    }
+}
+
+pub(crate) fn parse(data: &[u8]) -> Result<Element, Error> {
+    let mut reader = NsReader::from_reader(data);
+    let result = parse_events(&mut reader);
+    result.map_err(|err| match err {
+        Error::Xml { source, .. } => Error::Xml {
+            source,
+            position: Some(Position::from_offset(
+                data,
+                reader.error_position() as usize,
+            )),
+        },
+        other => other,
+    })
+}
+
+// Position of the first syntax error of a document, if any
+pub(crate) fn syntax_error_position(data: &[u8]) -> Option<Position> {
+    let mut reader = Reader::from_reader(data);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => return None,
+            Ok(_) => continue,
+            Err(_) => {
+                return Some(Position::from_offset(
+                    data,
+                    reader.error_position() as usize,
+                ))
+            }
+        }
+    }
+}
+
+fn parse_events(reader: &mut NsReader<&[u8]>) -> Result<Element, Error> {
+    reader.config_mut().expand_empty_elements = true;
+    let mut stack: Vec<Element> = Vec::new();
+    loop {
+        match reader.read_resolved_event()? {
+            (ns, Event::Start(e)) => {
+                let namespace: Option<String> = match ns {
+                    ResolveResult::Bound(n) => {
+                        Some(String::from_utf8_lossy(n.into_inner()).into_owned())
+                    }
+                    _ => None,
+                };
+                let mut attrs: BTreeMap<String, String> = BTreeMap::new();
+                for attr in e.attributes().with_checks(false) {
+                    let attr = attr?;
+                    let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+                    let value = attr
+                        .decoded_and_normalized_value(XmlVersion::Implicit1_0, reader.decoder())?
+                        .into_owned();
+                    attrs.insert(key, value);
+                }
+                stack.push(Element {
+                    name: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+                    namespace,
+                    attrs,
+                    ..Default::default()
+                });
+            }
+            (_, Event::End(_)) => {
+                let element = stack
+                    .pop()
+                    .ok_or_else(|| Error::InvalidFeed("unexpected closing tag".into()))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element),
+                }
+            }
+            (_, Event::Text(e)) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&e.decode()?);
+                }
+            }
+            (_, Event::CData(e)) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&e.decode()?);
+                }
+            }
+            (_, Event::GeneralRef(e)) => {
+                if let Some(current) = stack.last_mut() {
+                    let entity = e.decode()?;
+                    if let Some(resolved) = resolve_predefined_entity(&entity) {
+                        current.text.push_str(resolved);
+                    } else if let Some(ch) = e.resolve_char_ref()? {
+                        current.text.push(ch);
+                    } else {
+                        return Err(Error::InvalidFeed(format!(
+                            "undeclared entity: &{};",
+                            entity
+                        )));
+                    }
+                }
+            }
+            (_, Event::Eof) => return Err(Error::InvalidFeed("unexpected end of document".into())),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn write_text_element<W: Write>(
+    writer: &mut Writer<W>,
+    name: &str,
+    text: &str,
+) -> Result<(), Error> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+pub(crate) fn write_extension<W: Write>(
+    writer: &mut Writer<W>,
+    extension: &RssExtension,
+) -> Result<(), Error> {
+    let mut start = BytesStart::new(extension.name.as_str());
+    for (key, value) in extension.attrs.iter() {
+        start.push_attribute((key.as_str(), value.as_str()));
+    }
+    if extension.value.is_none() && extension.children.is_empty() {
+        writer.write_event(Event::Empty(start))?;
+        return Ok(());
+    }
+    writer.write_event(Event::Start(start))?;
+    if let Some(value) = extension.value.as_deref() {
+        writer.write_event(Event::Text(BytesText::new(value)))?;
+    }
+    for children in extension.children.values() {
+        for child in children {
+            write_extension(writer, child)?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new(extension.name.as_str())))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tree() {
+        let data = br#"<a:root xmlns:a="urn:a" xmlns="urn:default" a:id="1">
+            <child>one &amp; <![CDATA[two]]></child>
+            <empty/>
+        </a:root>"#;
+        let root = parse(data).unwrap();
+        assert!(root.is("urn:a", "root"));
+        assert_eq!(root.prefix(), Some("a"));
+        assert_eq!(root.attr("id"), Some(&"1".to_string()));
+        assert_eq!(root.namespaces().get("a"), Some(&"urn:a".to_string()));
+        assert_eq!(root.children.len(), 2);
+        assert!(root.children[0].is("urn:default", "child"));
+        assert_eq!(root.children[0].value(), Some("one & two".into()));
+        assert_eq!(root.children[1].value(), None);
+    }
+    #[test]
+    fn parse_truncated() {
+        assert!(parse(b"<root><child>").is_err());
+    }
+    #[test]
+    fn parse_error_position() {
+        let err = parse(b"<root>\n  <child></other>\n</root>").unwrap_err();
+        assert_eq!(err.position().map(|p| p.line), Some(2));
+        assert_eq!(
+            syntax_error_position(b"<root>\n  <child></other>\n</root>"),
+            err.position()
+        );
+        assert_eq!(syntax_error_position(b"<root></root>"), None);
+    }
+    #[test]
+    fn extension_no_loss() {
+        let data = br#"<media:content xmlns:media="urn:media" url="u"><media:title>T</media:title></media:content>"#;
+        let ext1: RssExtension = (&parse(data).unwrap()).into();
+        let mut writer = Writer::new(Vec::new());
+        write_extension(&mut writer, &ext1).unwrap();
+        let mut ext2: RssExtension = (&parse(&writer.into_inner()).unwrap()).into();
+        // namespace declaration is not written back
+        ext2.attrs.insert("xmlns:media".into(), "urn:media".into());
+        assert_eq!(ext1, ext2);
+    }
+}